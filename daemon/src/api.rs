@@ -0,0 +1,237 @@
+// Loopback HTTP API — lets integrations in any language drive the daemon.
+//
+//   POST /command   body: a TuiCommand as JSON → JSON array of resulting DaemonEvents
+//   GET  /events    server-sent events stream of DaemonEvents (NewMessage, ...)
+//
+// Bound to 127.0.0.1 only. Every request needs `Authorization: Bearer <token>`,
+// where the token is read from (or generated into) `<data dir>/api-token`.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use trassenger_lib::storage;
+
+use crate::ipc::{self, DaemonEvent, IpcState, TuiCommand, TuiEventSender};
+
+/// Largest request body accepted (contact cards and messages are small)
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Interval between SSE keep-alive comments (also detects closed streams)
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+// ── Token ─────────────────────────────────────────────────────────────────────
+
+fn token_path() -> Result<std::path::PathBuf, String> {
+    Ok(storage::get_app_data_dir()?.join("api-token"))
+}
+
+/// Load the API bearer token, generating one on first use.
+fn load_or_create_token() -> Result<String, String> {
+    let path = token_path()?;
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        let token = token.trim().to_string();
+        if !token.is_empty() {
+            restrict_permissions(&path)?;
+            return Ok(token);
+        }
+        // Left empty by an interrupted first run
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to replace empty API token: {}", e))?;
+    }
+
+    let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+
+    // Owner-only from the start: the token grants full control of the daemon
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).map_err(|e| format!("Failed to create API token: {}", e))?;
+    std::io::Write::write_all(&mut file, token.as_bytes()).map_err(|e| format!("Failed to write API token: {}", e))?;
+
+    Ok(token)
+}
+
+/// Make the token file owner-only, refusing to serve the API if we can't.
+fn restrict_permissions(path: &std::path::Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict API token permissions: {}", e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Spawn the HTTP API in a background thread with its own tokio runtime.
+pub fn start_api_server(port: u16, state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender) {
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().expect("API tokio runtime");
+        rt.block_on(serve(port, state, tui_sender));
+    });
+}
+
+async fn serve(port: u16, state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender) {
+    let token = match load_or_create_token() {
+        Ok(t) => Arc::new(t),
+        Err(e) => {
+            eprintln!("[api] {}. HTTP API disabled.", e);
+            return;
+        }
+    };
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        let tui_sender = tui_sender.clone();
+        let token = token.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                handle_request(req, state.clone(), tui_sender.clone(), token.clone())
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(make_svc),
+        Err(e) => {
+            eprintln!("[api] Failed to bind {}: {}", addr, e);
+            return;
+        }
+    };
+
+    eprintln!("[api] Listening on http://{}", addr);
+
+    if let Err(e) = server.await {
+        eprintln!("[api] Server error: {}", e);
+    }
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    state: Arc<Mutex<IpcState>>,
+    tui_sender: TuiEventSender,
+    token: Arc<String>,
+) -> Result<Response<Body>, Infallible> {
+    if !is_authorized(&req, &token) {
+        return Ok(json_error(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token"));
+    }
+
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::POST, "/command") => handle_command(req, &state).await,
+        (&Method::GET, "/events") => handle_events(state, tui_sender),
+        _ => json_error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    Ok(response)
+}
+
+fn is_authorized(req: &Request<Body>, token: &str) -> bool {
+    let provided = req.headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    // Constant-time comparison
+    provided.len() == token.len()
+        && provided.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+// ── Routes ────────────────────────────────────────────────────────────────────
+
+async fn handle_command(req: Request<Body>, state: &Arc<Mutex<IpcState>>) -> Response<Body> {
+    let body = match read_body(req.into_body()).await {
+        Ok(b) => b,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &e),
+    };
+
+    let cmd: TuiCommand = match serde_json::from_slice(&body) {
+        Ok(c) => c,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("Invalid command: {}", e)),
+    };
+
+    let events = ipc::handle_command(cmd, state).await;
+
+    // Surface daemon-side failures as HTTP errors
+    if let Some(DaemonEvent::Error { message }) = events.iter().find(|e| matches!(e, DaemonEvent::Error { .. })) {
+        return json_error(StatusCode::UNPROCESSABLE_ENTITY, message);
+    }
+
+    json_response(StatusCode::OK, &events)
+}
+
+fn handle_events(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender) -> Response<Body> {
+    let (mut body_tx, body) = Body::channel();
+    let (client_id, mut event_rx) = ipc::register_client(&tui_sender, &state);
+
+    tokio::spawn(async move {
+        loop {
+            let chunk = tokio::select! {
+                ev = event_rx.recv() => match ev {
+                    Some(event) => match serde_json::to_string(&event) {
+                        Ok(json) => format!("data: {}\n\n", json),
+                        Err(e) => {
+                            eprintln!("[api] Serialize error: {}", e);
+                            continue;
+                        }
+                    },
+                    None => break,
+                },
+                _ = tokio::time::sleep(SSE_KEEPALIVE) => ": keep-alive\n\n".to_string(),
+            };
+
+            if body_tx.send_data(Bytes::from(chunk)).await.is_err() {
+                break; // Client went away
+            }
+        }
+
+        drop(event_rx);
+        ipc::unregister_client(&tui_sender, &state, client_id);
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, "text/event-stream")
+        .header(hyper::header::CACHE_CONTROL, "no-cache")
+        .body(body)
+        .expect("valid SSE response")
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+async fn read_body(mut body: Body) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| format!("Read body: {}", e))?;
+        if buf.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err("Request body too large".to_string());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf)
+}
+
+fn json_response<T: serde::Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let json = serde_json::to_string(value).unwrap_or_else(|_| "null".to_string());
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json))
+        .expect("valid JSON response")
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
@@ -0,0 +1,114 @@
+// User hook scripts — run configured commands when daemon events happen
+// (new message, failed send, contact import).
+//
+// Each command runs through the system shell with a JSON description of the
+// event on stdin and TRASSENGER_EVENT set to the event name. Hooks run in the
+// background with a timeout so a slow script never stalls polling or sending.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use trassenger_lib::storage;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Hook {
+    NewMessage,
+    SendFailed,
+    ContactImported,
+}
+
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::NewMessage => "new_message",
+            Hook::SendFailed => "send_failed",
+            Hook::ContactImported => "contact_imported",
+        }
+    }
+
+    fn commands(self, hooks: &storage::HooksConfig) -> &[String] {
+        match self {
+            Hook::NewMessage => &hooks.on_message,
+            Hook::SendFailed => &hooks.on_send_failed,
+            Hook::ContactImported => &hooks.on_contact_imported,
+        }
+    }
+}
+
+/// Run every command configured for `hook` in the background.
+/// Must be called from within a tokio runtime.
+pub fn fire(hook: Hook, mut payload: serde_json::Value) {
+    // Read config on each event so edits apply without a restart
+    let hooks = match storage::load_config() {
+        Ok(c) => c.hooks,
+        Err(_) => return,
+    };
+    let commands = hook.commands(&hooks).to_vec();
+    if commands.is_empty() {
+        return;
+    }
+
+    payload["event"] = hook.name().into();
+    let input = payload.to_string();
+    let timeout = Duration::from_secs(hooks.timeout_secs);
+
+    for command in commands {
+        let input = input.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_hook(&command, hook, &input, timeout).await {
+                eprintln!("[hooks] {} hook `{}` failed: {}", hook.name(), command, e);
+            }
+        });
+    }
+}
+
+/// Name of the contact owning `queue_id`, for hook payloads
+pub fn peer_name(queue_id: &str) -> Option<String> {
+    storage::load_peers()
+        .ok()?
+        .into_iter()
+        .find(|p| p.queue_id == queue_id)
+        .map(|p| p.name)
+}
+
+async fn run_hook(command: &str, hook: Hook, input: &str, timeout: Duration) -> Result<(), String> {
+    let mut child = shell_command(command)
+        .env("TRASSENGER_EVENT", hook.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("spawn: {}", e))?;
+
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The script may not read stdin at all — that's fine
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+        child.wait().await
+    };
+
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("exited with {}", status)),
+        Ok(Err(e)) => Err(format!("wait: {}", e)),
+        Err(_) => Err(format!("timed out after {}s", timeout.as_secs())), // killed on drop
+    }
+}
+
+fn shell_command(command: &str) -> tokio::process::Command {
+    #[cfg(windows)]
+    {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    }
+    #[cfg(not(windows))]
+    {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
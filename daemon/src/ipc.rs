@@ -1,8 +1,9 @@
 // IPC layer for daemon — listens on a local socket, handles TUI commands,
 // pushes events back to connected TUI.
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use trassenger_lib::{crypto, crypto::Keypair, storage};
+use trassenger_lib::{contact_card::{self, CardTrust, ContactCard}, crypto, crypto::Keypair, mailbox::MailboxError, polling_policy::PollingPolicy, storage, transport::{self, Transport}};
 
 // ── Socket path ───────────────────────────────────────────────────────────────
 
@@ -28,43 +29,136 @@ pub enum IpcSignal {
     TuiDisconnected,
     /// TUI requests interval reset (user just sent a message)
     ResetPollingInterval,
+    /// Config was saved and the transport rebuilt — pick up the new settings
+    ConfigChanged,
+    /// Queues to watch changed (e.g. a key change or a new invite) — resubscribe and poll now
+    QueuesChanged,
 }
 
 /// Shared state for IPC, updated by polling thread
 pub struct IpcState {
     pub keypair: Option<Keypair>,
     pub server_url: String,
+    /// Message transport shared by the polling loop and the send path
+    pub transport: Arc<dyn Transport>,
     /// Sender to notify polling thread of TUI connect/disconnect
     pub signal_tx: tokio::sync::mpsc::UnboundedSender<IpcSignal>,
     /// Current adaptive interval (pushed here by polling thread)
     pub current_interval_secs: u64,
+    /// Set while the mailbox's certificate fails its pin (see `Config::tls`)
+    pub certificate_warning: Option<String>,
+    /// Transports for contacts who read from other mailbox servers, keyed by
+    /// their server list
+    pub peer_transports: HashMap<Vec<String>, Arc<dyn Transport>>,
+}
+
+impl IpcState {
+    /// Where to post messages for a contact: the servers their card listed,
+    /// or ours when it listed none (or we aren't using HTTP mailboxes)
+    fn send_transport(&mut self, peer_mailboxes: Vec<String>, config: &storage::Config) -> Arc<dyn Transport> {
+        if peer_mailboxes.is_empty() || !config.transport.is_default() || peer_mailboxes == config.server_urls() {
+            return self.transport.clone();
+        }
+        self.peer_transports
+            .entry(peer_mailboxes)
+            .or_insert_with_key(|urls| transport::peer_transport(urls, config))
+            .clone()
+    }
 }
 
 // ── Commands from TUI ─────────────────────────────────────────────────────────
 
+/// What kind of program a connection belongs to. Only interactive TUIs
+/// switch polling to fast mode and hold back desktop notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+pub enum ClientRole {
+    Tui,
+    /// One-shot CLI commands, `watch`, the HTTP API: the default
+    #[default]
+    Cli,
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum TuiCommand {
+    /// First line on a connection: says which kind of client it is
+    Hello {
+        role: ClientRole,
+    },
     SendMessage {
         queue_id: String,
         plaintext: String,
         peer_encrypt_pk: String,
+        /// Reply only once the mailbox has the message (or the post failed)
+        #[serde(default)]
+        wait: bool,
     },
     LoadMessages {
         queue_id: String,
     },
     LoadPeers,
+    /// `json` is a contact card, or an invite code
     ImportContact {
         json: String,
     },
+    /// `print_only` just returns the card: no Downloads file, no remembered
+    /// display name, and a recent rendezvous is reused instead of opening one
     ExportContact {
         name: String,
+        #[serde(default)]
+        print_only: bool,
+    },
+    RenameContact {
+        queue_id: String,
+        name: String,
+    },
+    /// Remove a contact, and with `purge_history` their conversation too
+    DeleteContact {
+        queue_id: String,
+        #[serde(default)]
+        purge_history: bool,
+    },
+    BlockContact {
+        queue_id: String,
+        blocked: bool,
+    },
+    SetContactNotes {
+        queue_id: String,
+        notes: String,
+    },
+    GetSafetyNumber {
+        queue_id: String,
+    },
+    /// Cards that answered our invites, waiting for approval
+    LoadContactRequests,
+    /// Add the request's sender as a contact
+    AcceptContactRequest {
+        id: String,
+    },
+    /// Drop the request and any messages it brought; with `block`, add the
+    /// sender as a blocked contact so their inbox messages are discarded
+    RejectContactRequest {
+        id: String,
+        #[serde(default)]
+        block: bool,
+    },
+    /// Record whether the contact's safety number was checked out of band
+    VerifyContact {
+        queue_id: String,
+        verified: bool,
     },
     UpdateConfig {
         server_url: String,
         polling_interval_secs: u64,
+        /// Absent from older clients: keep the saved policy
+        #[serde(default)]
+        polling: Option<PollingPolicy>,
+        /// Absent from older clients: keep the saved setting
+        #[serde(default)]
+        public_inbox: Option<bool>,
     },
     ResetPollingInterval,
+    GetStatus,
 }
 
 // ── Events to TUI ─────────────────────────────────────────────────────────────
@@ -84,24 +178,66 @@ pub enum DaemonEvent {
     },
     ContactImported {
         peer: storage::Peer,
+        /// Set when the card was accepted despite being unsigned
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warning: Option<String>,
+    },
+    /// A re-import replaced a known contact's keys
+    ContactKeyChanged {
+        peer: storage::Peer,
     },
     ContactExported {
         json: String,
+        invite: String,
+        /// Where the card file was written, if anywhere
+        saved_to: Option<String>,
+    },
+    SafetyNumber {
+        queue_id: String,
+        groups: Vec<String>,
+    },
+    ContactRequests {
+        requests: Vec<storage::ContactRequest>,
+    },
+    /// Someone answered one of our invites
+    ContactRequest {
+        request: storage::ContactRequest,
     },
     MessageSent,
     PollingInterval {
         secs: u64,
     },
+    Status {
+        server_url: String,
+        polling_interval_secs: u64,
+        peer_count: usize,
+        keypair_loaded: bool,
+    },
     Error {
         message: String,
     },
+    /// The mailbox's certificate stopped (Some) or resumed (None) matching its pin
+    CertificateWarning {
+        message: Option<String>,
+    },
+    /// New settings are in effect
+    ConfigChanged {
+        config: storage::Config,
+    },
 }
 
 // ── Sender handle for pushing events to connected TUI ────────────────────────
 
-/// Cloneable handle to send events to the currently connected TUI session.
-/// Wrapped in Arc<Mutex<Option<...>>> so the polling thread can push NewMessage.
-pub type TuiEventSender = Arc<Mutex<Option<tokio::sync::mpsc::UnboundedSender<DaemonEvent>>>>;
+/// A connected client's event channel
+pub struct ClientHandle {
+    id: u64,
+    role: ClientRole,
+    tx: tokio::sync::mpsc::UnboundedSender<DaemonEvent>,
+}
+
+/// Cloneable handle to send events to every connected client (TUI or CLI).
+/// Wrapped in Arc<Mutex<Vec<...>>> so the polling thread can push NewMessage.
+pub type TuiEventSender = Arc<Mutex<Vec<ClientHandle>>>;
 
 // ── Main IPC listener ────────────────────────────────────────────────────────
 
@@ -137,32 +273,8 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
     loop {
         match listener.accept().await {
             Ok((stream, _)) => {
-                eprintln!("[ipc] TUI connected");
-                // Signal polling thread: switch to fast polling
-                {
-                    if let Ok(s) = state.lock() {
-                        let _ = s.signal_tx.send(IpcSignal::TuiConnected);
-                    }
-                }
-
-                // Create event channel for this TUI session
-                let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<DaemonEvent>();
-
-                // Register sender so polling thread can push NewMessage
-                {
-                    if let Ok(mut guard) = tui_sender.lock() {
-                        *guard = Some(event_tx.clone());
-                    }
-                }
-
-                // Send current polling interval immediately on connect
-                {
-                    if let Ok(s) = state.lock() {
-                        let _ = event_tx.send(DaemonEvent::PollingInterval {
-                            secs: s.current_interval_secs,
-                        });
-                    }
-                }
+                eprintln!("[ipc] Client connected");
+                let (client_id, mut event_rx) = register_client(&tui_sender, &state);
 
                 let state_clone = state.clone();
                 let tui_sender_clone = tui_sender.clone();
@@ -181,6 +293,9 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                                 match line {
                                     Ok(Some(json)) => {
                                         match serde_json::from_str::<TuiCommand>(&json) {
+                                            Ok(TuiCommand::Hello { role }) => {
+                                                set_client_role(&tui_sender_clone, &state_clone, client_id, role);
+                                            }
                                             Ok(cmd) => {
                                                 let events = handle_command(cmd, &state_clone).await;
                                                 for ev in events {
@@ -203,7 +318,7 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                                         }
                                     }
                                     Ok(None) => {
-                                        eprintln!("[ipc] TUI disconnected");
+                                        eprintln!("[ipc] Client disconnected");
                                         break;
                                     }
                                     Err(e) => {
@@ -235,13 +350,9 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                         }
                     }
 
-                    // TUI disconnected — clear sender, signal polling thread
-                    if let Ok(mut guard) = tui_sender_clone.lock() {
-                        *guard = None;
-                    }
-                    if let Ok(s) = state_clone.lock() {
-                        let _ = s.signal_tx.send(IpcSignal::TuiDisconnected);
-                    }
+                    // TUI disconnected — drop its sender, signal polling thread once the last client is gone
+                    drop(event_rx);
+                    unregister_client(&tui_sender_clone, &state_clone, client_id);
                 });
             }
             Err(e) => {
@@ -277,31 +388,8 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
             continue;
         }
 
-        eprintln!("[ipc] TUI connected via named pipe");
-
-        // Signal polling thread
-        {
-            if let Ok(s) = state.lock() {
-                let _ = s.signal_tx.send(IpcSignal::TuiConnected);
-            }
-        }
-
-        let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<DaemonEvent>();
-
-        {
-            if let Ok(mut guard) = tui_sender.lock() {
-                *guard = Some(event_tx.clone());
-            }
-        }
-
-        // Send current interval
-        {
-            if let Ok(s) = state.lock() {
-                let _ = event_tx.send(DaemonEvent::PollingInterval {
-                    secs: s.current_interval_secs,
-                });
-            }
-        }
+        eprintln!("[ipc] Client connected via named pipe");
+        let (client_id, mut event_rx) = register_client(&tui_sender, &state);
 
         let state_clone = state.clone();
         let tui_sender_clone = tui_sender.clone();
@@ -318,6 +406,9 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                         match line {
                             Ok(Some(json)) => {
                                 match serde_json::from_str::<TuiCommand>(&json) {
+                                    Ok(TuiCommand::Hello { role }) => {
+                                        set_client_role(&tui_sender_clone, &state_clone, client_id, role);
+                                    }
                                     Ok(cmd) => {
                                         let events = handle_command(cmd, &state_clone).await;
                                         for ev in events {
@@ -338,7 +429,7 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                                 }
                             }
                             Ok(None) | Err(_) => {
-                                eprintln!("[ipc] TUI disconnected");
+                                eprintln!("[ipc] Client disconnected");
                                 break;
                             }
                         }
@@ -364,34 +455,134 @@ async fn ipc_accept_loop(state: Arc<Mutex<IpcState>>, tui_sender: TuiEventSender
                 }
             }
 
-            if let Ok(mut guard) = tui_sender_clone.lock() {
-                *guard = None;
-            }
-            if let Ok(s) = state_clone.lock() {
-                let _ = s.signal_tx.send(IpcSignal::TuiDisconnected);
-            }
+            drop(event_rx);
+            unregister_client(&tui_sender_clone, &state_clone, client_id);
         });
     }
 }
 
+/// Register a new client session (as `ClientRole::Cli` until it says
+/// otherwise) and return its id and the receiver for events pushed to it.
+/// Sends the current interval and any certificate warning.
+pub fn register_client(
+    tui_sender: &TuiEventSender,
+    state: &Arc<Mutex<IpcState>>,
+) -> (u64, tokio::sync::mpsc::UnboundedReceiver<DaemonEvent>) {
+    static NEXT_CLIENT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+    let id = NEXT_CLIENT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel::<DaemonEvent>();
+
+    if let Ok(s) = state.lock() {
+        let _ = event_tx.send(DaemonEvent::PollingInterval { secs: s.current_interval_secs });
+        if let Some(message) = &s.certificate_warning {
+            let _ = event_tx.send(DaemonEvent::CertificateWarning { message: Some(message.clone()) });
+        }
+    }
+
+    // Register sender so polling thread can push NewMessage
+    if let Ok(mut guard) = tui_sender.lock() {
+        guard.push(ClientHandle { id, role: ClientRole::Cli, tx: event_tx });
+    }
+
+    (id, event_rx)
+}
+
+/// Record a client's `Hello`. The first interactive TUI switches polling to
+/// fast mode.
+pub fn set_client_role(tui_sender: &TuiEventSender, state: &Arc<Mutex<IpcState>>, id: u64, role: ClientRole) {
+    let first_tui = match tui_sender.lock() {
+        Ok(mut guard) => {
+            let was_connected = guard.iter().any(|c| c.role == ClientRole::Tui);
+            if let Some(client) = guard.iter_mut().find(|c| c.id == id) {
+                client.role = role;
+            }
+            !was_connected && role == ClientRole::Tui
+        }
+        Err(_) => false,
+    };
+    if first_tui {
+        if let Ok(s) = state.lock() {
+            let _ = s.signal_tx.send(IpcSignal::TuiConnected);
+        }
+    }
+}
+
+/// Forget a client and any closed senders; tell the polling thread when the
+/// last TUI is gone. Call after dropping the session's event receiver.
+pub fn unregister_client(tui_sender: &TuiEventSender, state: &Arc<Mutex<IpcState>>, id: u64) {
+    let last_tui_left = match tui_sender.lock() {
+        Ok(mut guard) => {
+            let was_connected = guard.iter().any(|c| c.role == ClientRole::Tui);
+            guard.retain(|c| c.id != id && !c.tx.is_closed());
+            was_connected && !guard.iter().any(|c| c.role == ClientRole::Tui)
+        }
+        Err(_) => false,
+    };
+    if last_tui_left {
+        if let Ok(s) = state.lock() {
+            let _ = s.signal_tx.send(IpcSignal::TuiDisconnected);
+        }
+    }
+}
+
 // ── Command handlers ──────────────────────────────────────────────────────────
 
-async fn handle_command(cmd: TuiCommand, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+pub async fn handle_command(cmd: TuiCommand, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
     match cmd {
+        // Only meaningful on a socket connection, which handles it itself
+        TuiCommand::Hello { .. } => vec![],
+
         TuiCommand::LoadPeers => handle_load_peers(),
 
         TuiCommand::LoadMessages { queue_id } => handle_load_messages(queue_id),
 
-        TuiCommand::SendMessage { queue_id, plaintext, peer_encrypt_pk } => {
-            handle_send_message(queue_id, plaintext, peer_encrypt_pk, state).await
+        TuiCommand::SendMessage { queue_id, plaintext, peer_encrypt_pk, wait } => {
+            handle_send_message(queue_id, plaintext, peer_encrypt_pk, wait, state).await
         }
 
         TuiCommand::ImportContact { json } => handle_import_contact(json, state),
 
-        TuiCommand::ExportContact { name } => handle_export_contact(name, state),
+        TuiCommand::ExportContact { name, print_only } => handle_export_contact(name, print_only, state),
 
-        TuiCommand::UpdateConfig { server_url, polling_interval_secs } => {
-            handle_update_config(server_url, polling_interval_secs)
+        TuiCommand::RenameContact { queue_id, name } => handle_rename_contact(queue_id, name),
+
+        TuiCommand::DeleteContact { queue_id, purge_history } => handle_delete_contact(queue_id, purge_history),
+
+        TuiCommand::BlockContact { queue_id, blocked } => {
+            update_peer(&queue_id, |peer| {
+                peer.blocked = blocked;
+                Ok(())
+            })
+        }
+
+        TuiCommand::SetContactNotes { queue_id, notes } => {
+            update_peer(&queue_id, |peer| {
+                peer.notes = notes.trim().to_string();
+                Ok(())
+            })
+        }
+
+        TuiCommand::GetSafetyNumber { queue_id } => handle_get_safety_number(queue_id, state),
+
+        TuiCommand::LoadContactRequests => handle_load_contact_requests(),
+
+        TuiCommand::AcceptContactRequest { id } => handle_accept_contact_request(id, state),
+
+        TuiCommand::RejectContactRequest { id, block } => handle_reject_contact_request(id, block, state),
+
+        TuiCommand::VerifyContact { queue_id, verified } => {
+            update_peer(&queue_id, |peer| {
+                peer.verified = verified;
+                if verified {
+                    peer.key_changed_at = None;
+                }
+                Ok(())
+            })
+        }
+
+        TuiCommand::UpdateConfig { server_url, polling_interval_secs, polling, public_inbox } => {
+            handle_update_config(server_url, polling_interval_secs, polling, public_inbox, state)
         }
 
         TuiCommand::ResetPollingInterval => {
@@ -401,6 +592,7 @@ async fn handle_command(cmd: TuiCommand, state: &Arc<Mutex<IpcState>>) -> Vec<Da
             vec![]
         }
 
+        TuiCommand::GetStatus => handle_get_status(state),
     }
 }
 
@@ -411,6 +603,16 @@ fn handle_load_peers() -> Vec<DaemonEvent> {
     }
 }
 
+fn handle_get_status(state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+    let (server_url, polling_interval_secs, keypair_loaded) = match state.lock() {
+        Ok(s) => (s.server_url.clone(), s.current_interval_secs, s.keypair.is_some()),
+        Err(_) => return vec![DaemonEvent::Error { message: "State lock poisoned".to_string() }],
+    };
+    let peer_count = storage::load_peers().map(|p| p.len()).unwrap_or(0);
+
+    vec![DaemonEvent::Status { server_url, polling_interval_secs, peer_count, keypair_loaded }]
+}
+
 fn handle_load_messages(queue_id: String) -> Vec<DaemonEvent> {
     match storage::init_message_db().and_then(|conn| storage::load_messages_for_queue(&conn, &queue_id)) {
         Ok(messages) => vec![DaemonEvent::Messages { queue_id, messages }],
@@ -422,14 +624,32 @@ async fn handle_send_message(
     queue_id: String,
     plaintext: String,
     peer_encrypt_pk: String,
+    wait: bool,
     state: &Arc<Mutex<IpcState>>,
 ) -> Vec<DaemonEvent> {
-    let (keypair, server_url) = {
-        let s = match state.lock() {
+    // Post to the recipient's mailbox; polling reads ours
+    let config = storage::load_config().unwrap_or_default();
+    let peer = storage::load_peers()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.queue_id == queue_id);
+    if let Some(peer) = peer.as_ref().filter(|p| p.blocked) {
+        return vec![DaemonEvent::Error { message: format!("{} is blocked — unblock them to send", peer.name) }];
+    }
+    // Until they write back they may not have added us, so a contact with a
+    // public inbox gets our messages there, with our card attached
+    let via_inbox = peer.as_ref().is_some_and(|p| p.inbox)
+        && !storage::init_message_db()
+            .and_then(|conn| storage::has_inbound_messages(&conn, &queue_id))
+            .unwrap_or(true);
+    let peer_mailboxes = peer.map(|p| p.mailboxes).unwrap_or_default();
+
+    let (keypair, transport) = {
+        let mut s = match state.lock() {
             Ok(s) => s,
             Err(_) => return vec![DaemonEvent::Error { message: "State lock poisoned".to_string() }],
         };
-        (s.keypair.clone(), s.server_url.clone())
+        (s.keypair.clone(), s.send_transport(peer_mailboxes, &config))
     };
 
     let keypair = match keypair {
@@ -442,40 +662,20 @@ async fn handle_send_message(
         Err(e) => return vec![DaemonEvent::Error { message: format!("Invalid peer_encrypt_pk: {}", e) }],
     };
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-
-    let payload = serde_json::json!({
-        "type": "text",
-        "content": plaintext,
-        "timestamp": timestamp,
-        "sender_id": crypto::to_hex(&keypair.encrypt_pk),
-    });
+    let timestamp = unix_now();
 
-    let payload_bytes = match serde_json::to_vec(&payload) {
-        Ok(b) => b,
-        Err(e) => return vec![DaemonEvent::Error { message: format!("Serialize payload: {}", e) }],
+    let sealed = if via_inbox {
+        inbox_content(&keypair, &config, &plaintext).and_then(|content| {
+            seal_envelope(&keypair, &recipient_encrypt_pk, "inbox", &content, timestamp)
+        })
+    } else {
+        seal_envelope(&keypair, &recipient_encrypt_pk, "text", &plaintext, timestamp)
     };
-
-    let mut message_to_sign = keypair.encrypt_pk.clone();
-    let encrypted = match crypto::encrypt_message(&payload_bytes, &recipient_encrypt_pk, &keypair.encrypt_sk) {
-        Ok(e) => e,
-        Err(e) => return vec![DaemonEvent::Error { message: format!("Encrypt: {}", e) }],
-    };
-    message_to_sign.extend(encrypted);
-
-    let signed = match crypto::sign_message(&message_to_sign, &keypair.sign_sk) {
-        Ok(s) => s,
-        Err(e) => return vec![DaemonEvent::Error { message: format!("Sign: {}", e) }],
+    let (encoded, delete_hash) = match sealed {
+        Ok(sealed) => sealed,
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
     };
-
-    let mut final_message = keypair.sign_pk.clone();
-    final_message.extend(signed);
-
-    use base64::{Engine as _, engine::general_purpose};
-    let encoded = general_purpose::STANDARD.encode(&final_message);
+    let post_queue_id = if via_inbox { crypto::inbox_queue_id(&recipient_encrypt_pk) } else { queue_id.clone() };
 
     let local_id = uuid::Uuid::new_v4().to_string();
 
@@ -499,43 +699,114 @@ async fn handle_send_message(
         return vec![DaemonEvent::Error { message: "Failed to save message to DB".to_string() }];
     }
 
-    // Send to server async — return local_id immediately
-    let local_id_clone = local_id.clone();
-    let queue_id_clone = queue_id.clone();
-    tokio::spawn(async move {
-        use trassenger_lib::mailbox::{MailboxClient, MessageMeta};
-        let client = MailboxClient::new(server_url);
-        match client.send_message(&queue_id_clone, encoded, MessageMeta { filename: None, size: None }).await {
+    let post = async move {
+        use trassenger_lib::mailbox::MessageMeta;
+        let meta = MessageMeta { filename: None, size: None, delete_hash };
+        match transport.post(&post_queue_id, encoded, meta).await {
             Ok(_) => {
                 // Update status to "sent"
                 if let Ok(conn) = storage::init_message_db() {
                     let _ = conn.execute(
                         "UPDATE messages SET status = 'sent' WHERE id = ?1",
-                        [&local_id_clone],
+                        [&local_id],
                     );
                 }
+                Ok(())
             }
             Err(e) => {
                 eprintln!("[ipc] Failed to send message to server: {}", e);
                 if let Ok(conn) = storage::init_message_db() {
                     let _ = conn.execute(
                         "UPDATE messages SET status = 'failed' WHERE id = ?1",
-                        [&local_id_clone],
+                        [&local_id],
                     );
                 }
+                let failed = storage::Message { status: "failed".to_string(), ..local_message };
+                crate::hooks::fire(crate::hooks::Hook::SendFailed, serde_json::json!({
+                    "to": crate::hooks::peer_name(&queue_id),
+                    "message": failed,
+                    "error": e.to_string(),
+                }));
+                Err(e.to_string())
             }
         }
+    };
+
+    // The TUI shows the message as "sending" straight away; the CLI waits
+    // so its exit code reflects the outcome
+    if wait {
+        match post.await {
+            Ok(()) => vec![DaemonEvent::MessageSent],
+            Err(e) => vec![DaemonEvent::Error { message: e }],
+        }
+    } else {
+        tokio::spawn(post);
+        vec![DaemonEvent::MessageSent]
+    }
+}
+
+/// Body of an inbox message: our signed card, so a recipient who hasn't
+/// added us can, and the text
+fn inbox_content(keypair: &Keypair, config: &storage::Config, text: &str) -> Result<String, String> {
+    let card = ContactCard::new_signed(&display_name(config), keypair, advertised_mailboxes(config), None, config.public_inbox)?;
+    Ok(serde_json::json!({ "card": card, "text": text }).to_string())
+}
+
+/// Encrypt a payload to the recipient and sign it. Returns the base64
+/// envelope and the hash of its deletion token for the server.
+fn seal_envelope(
+    keypair: &Keypair,
+    recipient_encrypt_pk: &[u8],
+    msg_type: &str,
+    content: &str,
+    timestamp: i64,
+) -> Result<(String, Option<String>), String> {
+    let payload = serde_json::json!({
+        "type": msg_type,
+        "content": content,
+        "timestamp": timestamp,
+        "sender_id": crypto::to_hex(&keypair.encrypt_pk),
     });
+    let payload_bytes = serde_json::to_vec(&payload).map_err(|e| format!("Serialize payload: {}", e))?;
+
+    let mut message_to_sign = keypair.encrypt_pk.clone();
+    let encrypted = crypto::encrypt_message(&payload_bytes, recipient_encrypt_pk, &keypair.encrypt_sk)
+        .map_err(|e| format!("Encrypt: {}", e))?;
+    message_to_sign.extend(encrypted);
+
+    let signed = crypto::sign_message(&message_to_sign, &keypair.sign_sk).map_err(|e| format!("Sign: {}", e))?;
+
+    let mut final_message = keypair.sign_pk.clone();
+    final_message.extend(signed);
 
-    let _ = local_id;
-    vec![DaemonEvent::MessageSent]
+    use base64::{Engine as _, engine::general_purpose};
+    let encoded = general_purpose::STANDARD.encode(&final_message);
+
+    // Only we and the recipient can derive the token; the server just gets its hash
+    let delete_hash = crypto::derive_delete_token(&keypair.encrypt_sk, recipient_encrypt_pk, encoded.as_bytes())
+        .map(|token| crypto::to_hex(&crypto::delete_commitment(&token)))
+        .ok();
+
+    Ok((encoded, delete_hash))
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
 }
 
 fn handle_import_contact(json: String, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
-    // Parse JSON
-    let contact_data: serde_json::Value = match serde_json::from_str(&json) {
+    // Parse JSON, or unpack an invite code into the same card JSON
+    let parsed = if contact_card::is_invite(&json) {
+        ContactCard::from_invite(&json).and_then(|card| serde_json::to_value(card).map_err(|e| e.to_string()))
+    } else {
+        serde_json::from_str(&json).map_err(|e| format!("Invalid JSON: {}", e))
+    };
+    let contact_data: serde_json::Value = match parsed {
         Ok(d) => d,
-        Err(e) => return vec![DaemonEvent::Error { message: format!("Invalid JSON: {}", e) }],
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
     };
 
     let name = match contact_data["name"].as_str() {
@@ -560,6 +831,36 @@ fn handle_import_contact(json: String, state: &Arc<Mutex<IpcState>>) -> Vec<Daem
         return vec![DaemonEvent::Error { message: format!("Invalid sign_pk: {}", e) }];
     }
 
+    // Where the contact reads their mail; absent on older cards
+    let mailboxes: Vec<String> = match &contact_data["mailboxes"] {
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::Array(urls) => match urls.iter().map(|u| u.as_str()).collect::<Option<Vec<_>>>() {
+            Some(urls) => urls.into_iter().map(|u| u.trim_end_matches('/').to_string()).collect(),
+            None => return vec![DaemonEvent::Error { message: "'mailboxes' must be a list of URLs".to_string() }],
+        },
+        _ => return vec![DaemonEvent::Error { message: "'mailboxes' must be a list of URLs".to_string() }],
+    };
+    if let Some(bad) = mailboxes.iter().find(|u| !(u.starts_with("https://") || u.starts_with("http://"))) {
+        return vec![DaemonEvent::Error { message: format!("Invalid mailbox URL: {}", bad) }];
+    }
+
+    // Signed cards must verify; legacy unsigned ones import with a warning
+    let card: ContactCard = match serde_json::from_value(contact_data.clone()) {
+        Ok(c) => c,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Invalid contact card: {}", e) }],
+    };
+    let trust = match card.verify() {
+        Ok(trust) => trust,
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
+    };
+    let mut warning = match trust {
+        CardTrust::Signed => None,
+        CardTrust::Unsigned => {
+            eprintln!("[daemon] Importing unsigned contact card for {}", name);
+            Some("This contact card is unsigned (older format) — compare safety numbers before trusting it".to_string())
+        }
+    };
+
     let my_encrypt_pk = {
         let s = match state.lock() {
             Ok(s) => s,
@@ -574,11 +875,11 @@ fn handle_import_contact(json: String, state: &Arc<Mutex<IpcState>>) -> Vec<Daem
         }
     }
 
-    // Check duplicates
-    let existing = storage::load_peers().unwrap_or_default();
-    if existing.iter().any(|p| p.encrypt_pk == encrypt_pk) {
-        return vec![DaemonEvent::Error { message: "Contact already exists".to_string() }];
-    }
+    let peers = storage::load_peers().unwrap_or_default();
+    let known = match match_known_contact(&peers, &card, trust) {
+        Ok(k) => k,
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
+    };
 
     let my_pk_hex = my_encrypt_pk.unwrap_or_default();
     let queue_id = match crypto::generate_conversation_queue_id(&my_pk_hex, &encrypt_pk) {
@@ -586,20 +887,169 @@ fn handle_import_contact(json: String, state: &Arc<Mutex<IpcState>>) -> Vec<Daem
         Err(e) => return vec![DaemonEvent::Error { message: format!("Queue ID error: {}", e) }],
     };
 
+    let name = match known {
+        KnownContact::KeyChange(index) => return handle_key_change(peers, index, &card, queue_id, mailboxes, state),
+        KnownContact::New { name: saved_as } => {
+            if saved_as != name {
+                warning = Some(format!(
+                    "You already have a contact named {} with different keys — saved this one as {}. Compare safety numbers before trusting it",
+                    name, saved_as
+                ));
+            }
+            saved_as
+        }
+    };
+
     let peer = storage::Peer {
         name: name.clone(),
         encrypt_pk,
         sign_pk,
         queue_id,
+        mailboxes,
+        blocked: false,
+        verified: false,
+        key_changed_at: None,
+        notes: String::new(),
+        inbox: card.inbox,
     };
 
-    match storage::save_peer(&peer) {
-        Ok(_) => vec![DaemonEvent::ContactImported { peer }],
-        Err(e) => vec![DaemonEvent::Error { message: format!("Save peer failed: {}", e) }],
+    if let Err(e) = storage::save_peer(&peer) {
+        return vec![DaemonEvent::Error { message: format!("Save peer failed: {}", e) }];
+    }
+    crate::hooks::fire(crate::hooks::Hook::ContactImported, serde_json::json!({ "peer": &peer }));
+
+    // An invite asks for our card in return, so they can add us back
+    if let Some(rendezvous) = card.rendezvous {
+        if let Err(e) = send_card_to_rendezvous(&peer, rendezvous, state) {
+            eprintln!("[daemon] Failed to answer invite from {}: {}", peer.name, e);
+            warning = Some(format!("Couldn't send your card to {}: {}", peer.name, e));
+        }
+    }
+    if let Ok(s) = state.lock() {
+        let _ = s.signal_tx.send(IpcSignal::QueuesChanged);
+    }
+    vec![DaemonEvent::ContactImported { peer, warning }]
+}
+
+/// Answer an invite: post our own signed card, sealed to the inviter, to the
+/// rendezvous queue it named
+fn send_card_to_rendezvous(peer: &storage::Peer, rendezvous: String, state: &Arc<Mutex<IpcState>>) -> Result<(), String> {
+    let config = storage::load_config().unwrap_or_default();
+    let (keypair, transport) = {
+        let mut s = state.lock().map_err(|_| "State lock poisoned".to_string())?;
+        (s.keypair.clone(), s.send_transport(peer.mailboxes.clone(), &config))
+    };
+    let keypair = keypair.ok_or("Keypair not loaded")?;
+
+    let card = ContactCard::new_signed(&display_name(&config), &keypair, advertised_mailboxes(&config), None, config.public_inbox)?;
+    let card_json = serde_json::to_string(&card).map_err(|e| format!("Serialize: {}", e))?;
+    let recipient_encrypt_pk = crypto::from_hex(&peer.encrypt_pk)?;
+    let (encoded, delete_hash) = seal_envelope(&keypair, &recipient_encrypt_pk, "contact_card", &card_json, unix_now())?;
+
+    let peer_name = peer.name.clone();
+    tokio::spawn(async move {
+        use trassenger_lib::mailbox::MessageMeta;
+        let meta = MessageMeta { filename: None, size: None, delete_hash };
+        match transport.post(&rendezvous, encoded, meta).await {
+            Ok(_) => eprintln!("[daemon] Sent our contact card to {}", peer_name),
+            Err(e) => eprintln!("[daemon] Failed to send our contact card to {}: {}", peer_name, e),
+        }
+    });
+    Ok(())
+}
+
+/// Name on cards we send unprompted: the last one we exported under
+fn display_name(config: &storage::Config) -> String {
+    config
+        .display_name
+        .clone()
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| "Trassenger user".to_string())
+}
+
+/// Where others can reach us (a local folder means nothing to them)
+fn advertised_mailboxes(config: &storage::Config) -> Vec<String> {
+    if config.transport.is_default() { config.server_urls() } else { Vec::new() }
+}
+
+/// How an imported card relates to the contacts we already have
+#[derive(Debug, PartialEq)]
+enum KnownContact {
+    /// Someone new, to be saved under this name
+    New { name: String },
+    /// The contact at this index, with a new encryption key
+    KeyChange(usize),
+}
+
+/// Trust on first use: a card signed by a known contact's signing key but
+/// carrying a new encryption key is a key change. Anything weaker can't take
+/// over an existing contact, and a card that only shares a name is a
+/// different person, so they get a name of their own.
+fn match_known_contact(peers: &[storage::Peer], card: &ContactCard, trust: CardTrust) -> Result<KnownContact, String> {
+    if peers.iter().any(|p| p.encrypt_pk == card.encrypt_pk && p.sign_pk == card.sign_pk) {
+        return Err("Contact already exists".to_string());
+    }
+    if let Some(index) = peers.iter().position(|p| p.sign_pk == card.sign_pk) {
+        if trust != CardTrust::Signed {
+            return Err(format!("This unsigned card claims {}'s key — ask them for a signed card", peers[index].name));
+        }
+        return Ok(KnownContact::KeyChange(index));
+    }
+    if let Some(known) = peers.iter().find(|p| p.encrypt_pk == card.encrypt_pk) {
+        return Err(format!("This card reuses {}'s encryption key with a different signing key — not importing it", known.name));
     }
+
+    let taken = |name: &str| peers.iter().any(|p| p.name.eq_ignore_ascii_case(name));
+    if !taken(&card.name) {
+        return Ok(KnownContact::New { name: card.name.clone() });
+    }
+    let name = (2..)
+        .map(|n| format!("{} ({})", card.name, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| card.name.clone());
+    Ok(KnownContact::New { name })
 }
 
-fn handle_export_contact(name: String, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+/// A known contact presented a new encryption key: adopt it, flag the change
+/// and reset verification. Their history follows them to the new queue.
+fn handle_key_change(
+    mut peers: Vec<storage::Peer>,
+    index: usize,
+    card: &ContactCard,
+    queue_id: String,
+    mailboxes: Vec<String>,
+    state: &Arc<Mutex<IpcState>>,
+) -> Vec<DaemonEvent> {
+    let old_queue_id = std::mem::replace(&mut peers[index].queue_id, queue_id.clone());
+    let peer = &mut peers[index];
+    peer.encrypt_pk = card.encrypt_pk.clone();
+    peer.mailboxes = mailboxes;
+    peer.inbox = card.inbox;
+    peer.verified = false;
+    peer.key_changed_at = Some(unix_now());
+    let peer = peer.clone();
+
+    if let Err(e) = storage::save_peers(&peers) {
+        return vec![DaemonEvent::Error { message: format!("Save peer failed: {}", e) }];
+    }
+    if old_queue_id != queue_id {
+        if let Err(e) = storage::init_message_db().and_then(|conn| storage::move_messages_to_queue(&conn, &old_queue_id, &queue_id)) {
+            eprintln!("[daemon] Failed to move history for {}: {}", peer.name, e);
+        }
+    }
+
+    // Poll the new queue instead of the old one
+    if let Ok(s) = state.lock() {
+        let _ = s.signal_tx.send(IpcSignal::QueuesChanged);
+    }
+
+    eprintln!("[daemon] Key changed for contact {}", peer.name);
+    vec![DaemonEvent::ContactKeyChanged { peer }]
+}
+
+fn handle_export_contact(name: String, print_only: bool, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
     let keypair = {
         let s = match state.lock() {
             Ok(s) => s,
@@ -613,59 +1063,473 @@ fn handle_export_contact(name: String, state: &Arc<Mutex<IpcState>>) -> Vec<Daem
         None => return vec![DaemonEvent::Error { message: "Keypair not loaded".to_string() }],
     };
 
-    let contact_json = serde_json::json!({
-        "name": name,
-        "encrypt_pk": crypto::to_hex(&keypair.encrypt_pk),
-        "sign_pk": crypto::to_hex(&keypair.sign_pk),
-    });
+    let mut config = storage::load_config().unwrap_or_default();
+    let mailboxes = advertised_mailboxes(&config);
 
-    let json_string = match serde_json::to_string_pretty(&contact_json) {
+    // Remember the name for cards we send when answering others' invites
+    if !print_only && config.display_name.as_deref() != Some(name.as_str()) {
+        config.display_name = Some(name.clone());
+        if let Err(e) = storage::save_config(&config) {
+            eprintln!("[daemon] Failed to save display name: {}", e);
+        }
+    }
+
+    let rendezvous = match export_rendezvous(print_only, state) {
+        Ok(r) => r,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Save rendezvous: {}", e) }],
+    };
+
+    let card = match ContactCard::new_signed(&name, &keypair, mailboxes, Some(rendezvous), config.public_inbox) {
+        Ok(c) => c,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Sign contact card: {}", e) }],
+    };
+
+    let json_string = match serde_json::to_string_pretty(&card) {
         Ok(s) => s,
         Err(e) => return vec![DaemonEvent::Error { message: format!("Serialize: {}", e) }],
     };
 
-    // Save to Downloads folder
-    if let Some(home_dir) = dirs::home_dir() {
-        let filename = format!("contact-{}.json", name.replace(' ', "-"));
-        let path = home_dir.join("Downloads").join(&filename);
+    // Save to the Downloads folder, when there is one
+    let downloads = dirs::home_dir().map(|home| home.join("Downloads")).filter(|dir| dir.is_dir());
+    let mut saved_to = None;
+    if let (false, Some(dir)) = (print_only, downloads) {
+        let path = dir.join(format!("contact-{}.json", name.replace(' ', "-")));
         if let Err(e) = std::fs::write(&path, &json_string) {
             return vec![DaemonEvent::Error { message: format!("Write file: {}", e) }];
         }
+        saved_to = Some(path.display().to_string());
+    }
+
+    let invite = match card.to_invite() {
+        Ok(i) => i,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Invite code: {}", e) }],
+    };
+
+    vec![DaemonEvent::ContactExported { json: json_string, invite, saved_to }]
+}
+
+/// The one-time queue where whoever imports an exported card posts theirs.
+/// With `reuse`, one opened less than half its lifetime ago is handed out
+/// again, so repeated exports don't each add a queue to poll.
+fn export_rendezvous(reuse: bool, state: &Arc<Mutex<IpcState>>) -> Result<String, String> {
+    let ttl = trassenger_lib::config::RENDEZVOUS_TTL_SECS;
+    let now = unix_now();
+    let mut entries = storage::load_rendezvous().unwrap_or_default();
+    entries.retain(|r| now - r.created_at < ttl);
+
+    if reuse {
+        let recent = entries.iter().filter(|r| now - r.created_at < ttl / 2).max_by_key(|r| r.created_at);
+        if let Some(r) = recent {
+            return Ok(r.queue_id.clone());
+        }
+    }
+
+    let rendezvous = uuid::Uuid::new_v4().simple().to_string();
+    entries.push(storage::Rendezvous { queue_id: rendezvous.clone(), created_at: now });
+    storage::save_rendezvous(&entries)?;
+    if let Ok(s) = state.lock() {
+        let _ = s.signal_tx.send(IpcSignal::QueuesChanged);
+    }
+    Ok(rendezvous)
+}
+
+fn handle_load_contact_requests() -> Vec<DaemonEvent> {
+    match storage::load_contact_requests() {
+        Ok(requests) => vec![DaemonEvent::ContactRequests { requests }],
+        Err(e) => vec![DaemonEvent::Error { message: e }],
+    }
+}
+
+/// Import the request's card; the request is dropped once that succeeds
+fn handle_accept_contact_request(id: String, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+    let mut requests = storage::load_contact_requests().unwrap_or_default();
+    let Some(index) = requests.iter().position(|r| r.id == id) else {
+        return vec![DaemonEvent::Error { message: "Unknown contact request".to_string() }];
+    };
+    let json = match serde_json::to_string(&requests[index].card) {
+        Ok(j) => j,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Serialize: {}", e) }],
+    };
+
+    let mut events = handle_import_contact(json, state);
+    if events.iter().any(|e| matches!(e, DaemonEvent::Error { .. })) {
+        return events;
+    }
+    requests.remove(index);
+    if let Err(e) = storage::save_contact_requests(&requests) {
+        events.push(DaemonEvent::Error { message: format!("Save contact requests: {}", e) });
+    }
+    events.push(DaemonEvent::ContactRequests { requests });
+    events
+}
+
+fn handle_reject_contact_request(id: String, block: bool, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+    let mut requests = storage::load_contact_requests().unwrap_or_default();
+    let Some(index) = requests.iter().position(|r| r.id == id) else {
+        return vec![DaemonEvent::Error { message: "Unknown contact request".to_string() }];
+    };
+    let request = requests.remove(index);
+
+    let my_encrypt_pk = match state.lock() {
+        Ok(s) => s.keypair.as_ref().map(|kp| crypto::to_hex(&kp.encrypt_pk)).unwrap_or_default(),
+        Err(_) => return vec![DaemonEvent::Error { message: "State lock poisoned".to_string() }],
+    };
+    let queue_id = match crypto::generate_conversation_queue_id(&my_encrypt_pk, &request.card.encrypt_pk) {
+        Ok(q) => q,
+        Err(e) => return vec![DaemonEvent::Error { message: format!("Queue ID error: {}", e) }],
+    };
+
+    // Messages held for a stranger have no contact to belong to
+    let was_contact = storage::load_peers().unwrap_or_default().iter().any(|p| p.queue_id == queue_id);
+    if !was_contact {
+        if let Err(e) = storage::init_message_db().and_then(|conn| storage::delete_messages_for_queue(&conn, &queue_id)) {
+            eprintln!("[daemon] Failed to discard messages from {}: {}", request.card.name, e);
+        }
+    }
+
+    let mut events = Vec::new();
+    if block && was_contact {
+        events.extend(update_peer(&queue_id, |peer| {
+            peer.blocked = true;
+            Ok(())
+        }));
+    } else if block {
+        // A blocked contact: their queue isn't read and inbox messages are dropped
+        let peer = storage::Peer {
+            name: request.card.name.clone(),
+            encrypt_pk: request.card.encrypt_pk.clone(),
+            sign_pk: request.card.sign_pk.clone(),
+            queue_id: queue_id.clone(),
+            mailboxes: request.card.mailboxes.clone(),
+            blocked: true,
+            verified: false,
+            key_changed_at: None,
+            notes: String::new(),
+            inbox: request.card.inbox,
+        };
+        if let Err(e) = storage::save_peer(&peer) {
+            return vec![DaemonEvent::Error { message: format!("Save peer failed: {}", e) }];
+        }
+        events.extend(handle_load_peers());
+    }
+
+    match storage::save_contact_requests(&requests) {
+        Ok(_) => events.push(DaemonEvent::ContactRequests { requests }),
+        Err(e) => events.push(DaemonEvent::Error { message: format!("Save contact requests: {}", e) }),
+    }
+    events
+}
+
+/// Apply `change` to the contact on `queue_id` and send back the updated list
+fn update_peer(queue_id: &str, change: impl FnOnce(&mut storage::Peer) -> Result<(), String>) -> Vec<DaemonEvent> {
+    let mut peers = match storage::load_peers() {
+        Ok(p) => p,
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
+    };
+    let Some(peer) = peers.iter_mut().find(|p| p.queue_id == queue_id) else {
+        return vec![DaemonEvent::Error { message: "Unknown contact".to_string() }];
+    };
+    if let Err(e) = change(peer) {
+        return vec![DaemonEvent::Error { message: e }];
+    }
+
+    match storage::save_peers(&peers) {
+        Ok(_) => vec![DaemonEvent::Peers { peers }],
+        Err(e) => vec![DaemonEvent::Error { message: format!("Save peers failed: {}", e) }],
+    }
+}
+
+fn handle_rename_contact(queue_id: String, name: String) -> Vec<DaemonEvent> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return vec![DaemonEvent::Error { message: "Name cannot be empty".to_string() }];
+    }
+    // Names address contacts in the CLI and hooks, so keep them unique
+    let taken = storage::load_peers()
+        .unwrap_or_default()
+        .iter()
+        .any(|p| p.queue_id != queue_id && p.name.eq_ignore_ascii_case(&name));
+    if taken {
+        return vec![DaemonEvent::Error { message: format!("A contact named '{}' already exists", name) }];
+    }
+
+    update_peer(&queue_id, |peer| {
+        peer.name = name;
+        Ok(())
+    })
+}
+
+fn handle_get_safety_number(queue_id: String, state: &Arc<Mutex<IpcState>>) -> Vec<DaemonEvent> {
+    let keypair = match state.lock() {
+        Ok(s) => s.keypair.clone(),
+        Err(_) => return vec![DaemonEvent::Error { message: "State lock poisoned".to_string() }],
+    };
+    let Some(keypair) = keypair else {
+        return vec![DaemonEvent::Error { message: "Keypair not loaded".to_string() }];
+    };
+    let Some(peer) = storage::load_peers().unwrap_or_default().into_iter().find(|p| p.queue_id == queue_id) else {
+        return vec![DaemonEvent::Error { message: "Unknown contact".to_string() }];
+    };
+
+    let (their_sign_pk, their_encrypt_pk) = match (crypto::from_hex(&peer.sign_pk), crypto::from_hex(&peer.encrypt_pk)) {
+        (Ok(sign), Ok(encrypt)) => (sign, encrypt),
+        _ => return vec![DaemonEvent::Error { message: format!("Stored keys for {} are invalid", peer.name) }],
+    };
+    let groups = crypto::safety_number(&keypair.sign_pk, &keypair.encrypt_pk, &their_sign_pk, &their_encrypt_pk);
+    vec![DaemonEvent::SafetyNumber { queue_id, groups }]
+}
+
+fn handle_delete_contact(queue_id: String, purge_history: bool) -> Vec<DaemonEvent> {
+    let mut peers = match storage::load_peers() {
+        Ok(p) => p,
+        Err(e) => return vec![DaemonEvent::Error { message: e }],
+    };
+    let before = peers.len();
+    peers.retain(|p| p.queue_id != queue_id);
+    if peers.len() == before {
+        return vec![DaemonEvent::Error { message: "Unknown contact".to_string() }];
+    }
+    if let Err(e) = storage::save_peers(&peers) {
+        return vec![DaemonEvent::Error { message: format!("Save peers failed: {}", e) }];
     }
 
-    vec![DaemonEvent::ContactExported { json: json_string }]
+    if purge_history {
+        if let Err(e) = storage::init_message_db().and_then(|conn| storage::delete_messages_for_queue(&conn, &queue_id)) {
+            return vec![DaemonEvent::Peers { peers }, DaemonEvent::Error { message: e }];
+        }
+    }
+    vec![DaemonEvent::Peers { peers }]
 }
 
-fn handle_update_config(server_url: String, polling_interval_secs: u64) -> Vec<DaemonEvent> {
+fn handle_update_config(
+    server_url: String,
+    polling_interval_secs: u64,
+    polling: Option<PollingPolicy>,
+    public_inbox: Option<bool>,
+    state: &Arc<Mutex<IpcState>>,
+) -> Vec<DaemonEvent> {
+    // Keep settings the TUI doesn't edit (e.g. api_port)
+    let saved = storage::load_config().unwrap_or_default();
     let config = storage::Config {
-        server_url: server_url.clone(),
+        server_url,
         polling_interval_secs,
+        polling: polling.unwrap_or(saved.polling.clone()),
+        public_inbox: public_inbox.unwrap_or(saved.public_inbox),
+        ..saved
     };
-    match storage::save_config(&config) {
-        Ok(_) => vec![],
-        Err(e) => vec![DaemonEvent::Error { message: format!("Save config: {}", e) }],
+    if let Err(e) = config.polling.validate(config.polling_interval_secs) {
+        return vec![DaemonEvent::Error { message: e }];
+    }
+    if let Err(e) = storage::save_config(&config) {
+        return vec![DaemonEvent::Error { message: format!("Save config: {}", e) }];
+    }
+
+    // Swap in a transport for the new settings; the polling thread then
+    // resubscribes and tells every client (ConfigChanged)
+    match state.lock() {
+        Ok(mut s) => {
+            s.transport = transport::from_config(&config);
+            s.server_url = config.server_url.clone();
+            s.peer_transports.clear();
+            let _ = s.signal_tx.send(IpcSignal::ConfigChanged);
+            vec![]
+        }
+        Err(_) => vec![DaemonEvent::Error { message: "State lock poisoned".to_string() }],
     }
 }
 
-/// Push a NewMessage event to the connected TUI (if any).
+/// Push a NewMessage event to all connected clients.
 pub fn push_new_message(tui_sender: &TuiEventSender, message: storage::Message) {
-    if let Ok(guard) = tui_sender.lock() {
-        if let Some(tx) = guard.as_ref() {
-            let _ = tx.send(DaemonEvent::NewMessage { message });
+    push_event(tui_sender, DaemonEvent::NewMessage { message });
+}
+
+/// Push a ContactRequest event to all connected clients.
+pub fn push_contact_request(tui_sender: &TuiEventSender, request: storage::ContactRequest) {
+    push_event(tui_sender, DaemonEvent::ContactRequest { request });
+}
+
+/// Push a ConfigChanged event to all connected clients.
+pub fn push_config_changed(tui_sender: &TuiEventSender, config: storage::Config) {
+    push_event(tui_sender, DaemonEvent::ConfigChanged { config });
+}
+
+/// Push a PollingInterval event to all connected clients.
+pub fn push_polling_interval(tui_sender: &TuiEventSender, secs: u64) {
+    push_event(tui_sender, DaemonEvent::PollingInterval { secs });
+}
+
+/// Record the outcome of a poll against the mailbox: a pin mismatch raises the
+/// certificate warning, a clean poll clears it, other errors leave it alone.
+/// Clients are told only when it changes.
+pub fn note_poll_result(state: &Arc<Mutex<IpcState>>, tui_sender: &TuiEventSender, error: Option<&MailboxError>) {
+    let warning = match error {
+        Some(e @ MailboxError::PinMismatch(_)) => Some(e.to_string()),
+        Some(_) => return,
+        None => None,
+    };
+
+    let changed = match state.lock() {
+        Ok(mut s) if s.certificate_warning != warning => {
+            s.certificate_warning = warning.clone();
+            true
         }
+        _ => false,
+    };
+    if changed {
+        if let Some(message) = &warning {
+            eprintln!("[daemon] {}", message);
+        }
+        push_event(tui_sender, DaemonEvent::CertificateWarning { message: warning });
     }
 }
 
-/// Push a PollingInterval event to the connected TUI (if any).
-pub fn push_polling_interval(tui_sender: &TuiEventSender, secs: u64) {
+fn push_event(tui_sender: &TuiEventSender, event: DaemonEvent) {
     if let Ok(guard) = tui_sender.lock() {
-        if let Some(tx) = guard.as_ref() {
-            let _ = tx.send(DaemonEvent::PollingInterval { secs });
+        for client in guard.iter() {
+            let _ = client.tx.send(event.clone());
         }
     }
 }
 
-/// Returns true if a TUI is currently connected.
+/// Returns true if at least one interactive TUI is currently connected.
 pub fn is_tui_connected(tui_sender: &TuiEventSender) -> bool {
-    tui_sender.lock().ok().and_then(|g| g.as_ref().map(|_| ())).is_some()
+    tui_sender.lock().map(|g| g.iter().any(|c| c.role == ClientRole::Tui)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trassenger_lib::filesystem::FilesystemMailbox;
+
+    fn state(transport: Arc<dyn Transport>) -> (Arc<Mutex<IpcState>>, tokio::sync::mpsc::UnboundedReceiver<IpcSignal>) {
+        let (signal_tx, signal_rx) = tokio::sync::mpsc::unbounded_channel();
+        let state = IpcState {
+            keypair: Some(crypto::generate_keypair()),
+            server_url: String::new(),
+            transport,
+            signal_tx,
+            current_interval_secs: 10,
+            certificate_warning: None,
+            peer_transports: HashMap::new(),
+        };
+        (Arc::new(Mutex::new(state)), signal_rx)
+    }
+
+    /// Storage finds its directory through the process-wide
+    /// `TRASSENGER_DATA_DIR`, so tests using it take turns
+    static DATA_DIR: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Points storage at `dir` until dropped, then restores the previous value
+    struct DataDir {
+        previous: Option<std::ffi::OsString>,
+        _turn: tokio::sync::MutexGuard<'static, ()>,
+    }
+
+    impl DataDir {
+        async fn set(dir: &std::path::Path) -> Self {
+            let turn = DATA_DIR.lock().await;
+            let previous = std::env::var_os("TRASSENGER_DATA_DIR");
+            std::env::set_var("TRASSENGER_DATA_DIR", dir);
+            DataDir { previous, _turn: turn }
+        }
+    }
+
+    impl Drop for DataDir {
+        fn drop(&mut self) {
+            match self.previous.take() {
+                Some(value) => std::env::set_var("TRASSENGER_DATA_DIR", value),
+                None => std::env::remove_var("TRASSENGER_DATA_DIR"),
+            }
+        }
+    }
+
+    fn peer(name: &str, keys: &Keypair) -> storage::Peer {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "encrypt_pk": crypto::to_hex(&keys.encrypt_pk),
+            "sign_pk": crypto::to_hex(&keys.sign_pk),
+            "queue_id": format!("{}-queue", name),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_only_tuis_count_as_connected() {
+        let (state, mut signals) = state(Arc::new(FilesystemMailbox::new(std::env::temp_dir())));
+        let clients: TuiEventSender = Arc::new(Mutex::new(Vec::new()));
+
+        let (cli, _cli_events) = register_client(&clients, &state);
+        let (tui, _tui_events) = register_client(&clients, &state);
+        assert!(!is_tui_connected(&clients));
+        assert!(signals.try_recv().is_err());
+
+        set_client_role(&clients, &state, tui, ClientRole::Tui);
+        assert!(is_tui_connected(&clients));
+        assert!(matches!(signals.try_recv(), Ok(IpcSignal::TuiConnected)));
+
+        // A CLI command finishing doesn't end the TUI's session
+        unregister_client(&clients, &state, cli);
+        assert!(signals.try_recv().is_err());
+        unregister_client(&clients, &state, tui);
+        assert!(!is_tui_connected(&clients));
+        assert!(matches!(signals.try_recv(), Ok(IpcSignal::TuiDisconnected)));
+    }
+
+    #[test]
+    fn test_known_contact_matching() {
+        let (bob, mallory) = (crypto::generate_keypair(), crypto::generate_keypair());
+        let peers = vec![peer("Bob", &bob)];
+        let card = |name: &str, keys: &Keypair| ContactCard::new_signed(name, keys, vec![], None, false).unwrap();
+
+        assert_eq!(match_known_contact(&peers, &card("Bob", &bob), CardTrust::Signed), Err("Contact already exists".to_string()));
+
+        // Bob's signing key vouches for his new encryption key
+        let rotated = Keypair { encrypt_pk: crypto::generate_keypair().encrypt_pk, ..bob.clone() };
+        assert_eq!(match_known_contact(&peers, &card("Bob", &rotated), CardTrust::Signed), Ok(KnownContact::KeyChange(0)));
+        assert!(match_known_contact(&peers, &card("Bob", &rotated), CardTrust::Unsigned).is_err());
+
+        // Bob's encryption key under someone else's signature is refused
+        let posing = Keypair { encrypt_pk: bob.encrypt_pk.clone(), ..mallory.clone() };
+        assert!(match_known_contact(&peers, &card("Bob", &posing), CardTrust::Signed).is_err());
+
+        // Only the name in common: a separate contact
+        let expected = Ok(KnownContact::New { name: "bob (2)".to_string() });
+        assert_eq!(match_known_contact(&peers, &card("bob", &mallory), CardTrust::Signed), expected);
+        let expected = Ok(KnownContact::New { name: "Mallory".to_string() });
+        assert_eq!(match_known_contact(&peers, &card("Mallory", &mallory), CardTrust::Signed), expected);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_send_reports_the_post_result() {
+        let dir = std::env::temp_dir().join(format!("trassenger-daemon-{}", uuid::Uuid::new_v4()));
+        let _data_dir = DataDir::set(&dir).await;
+        storage::init_storage().unwrap();
+
+        let bob_pk = crypto::to_hex(&crypto::generate_keypair().encrypt_pk);
+        let send = |state: Arc<Mutex<IpcState>>| {
+            let cmd = TuiCommand::SendMessage {
+                queue_id: "bob-queue".to_string(),
+                plaintext: "hi".to_string(),
+                peer_encrypt_pk: bob_pk.clone(),
+                wait: true,
+            };
+            async move { handle_command(cmd, &state).await }
+        };
+
+        let (posted, _) = state(Arc::new(FilesystemMailbox::new(dir.join("mail"))));
+        assert!(matches!(send(posted).await.as_slice(), [DaemonEvent::MessageSent]));
+        assert_eq!(std::fs::read_dir(dir.join("mail").join("bob-queue")).unwrap().count(), 1);
+
+        // A file where the mailbox directory should be makes the post fail
+        std::fs::write(dir.join("not-a-dir"), "").unwrap();
+        let (failing, _) = state(Arc::new(FilesystemMailbox::new(dir.join("not-a-dir"))));
+        assert!(matches!(send(failing).await.as_slice(), [DaemonEvent::Error { .. }]));
+
+        let conn = storage::init_message_db().unwrap();
+        let mut statuses: Vec<String> = storage::load_messages_for_queue(&conn, "bob-queue").unwrap().into_iter().map(|m| m.status).collect();
+        statuses.sort();
+        assert_eq!(statuses, ["failed", "sent"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -2,7 +2,8 @@
 //
 // Architecture:
 //   main thread: tray icon + event loop (required by macOS)
-//   ipc thread:  local socket listener (one TUI connection at a time)
+//   ipc thread:  local socket listener (TUI and CLI clients)
+//   api thread:  optional loopback HTTP API (config.api_port)
 //   tokio thread: background polling (adaptive when TUI connected, 60s when not)
 
 // Hide the console window on Windows so only the tray icon appears
@@ -22,6 +23,8 @@ use tray_icon::{
 
 mod polling;
 mod ipc;
+mod api;
+mod hooks;
 
 /// Shared state between polling thread and main thread
 #[derive(Default)]
@@ -50,7 +53,7 @@ fn main() {
     #[cfg(unix)]
     {
         unsafe {
-            libc::signal(libc::SIGTERM, handle_sigterm as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, handle_sigterm as *const () as libc::sighandler_t);
         }
     }
 
@@ -63,25 +66,33 @@ fn main() {
     // IPC signal channel (IPC → polling thread)
     let (ipc_signal_tx, ipc_signal_rx) = tokio::sync::mpsc::unbounded_channel::<ipc::IpcSignal>();
 
-    // Load server URL for IPC state
-    let server_url = trassenger_lib::storage::load_config()
-        .map(|c| c.server_url)
-        .unwrap_or_else(|_| trassenger_lib::config::DEFAULT_SERVER_URL.to_string());
+    let config = trassenger_lib::storage::load_config().unwrap_or_default();
+    if let Some(proxy) = &config.proxy {
+        eprintln!("[daemon] Routing mailbox traffic through proxy {}", proxy);
+    }
 
     // Shared IPC state (keypair set by polling thread after it loads it)
     let ipc_state = Arc::new(Mutex::new(ipc::IpcState {
         keypair: None,
-        server_url,
+        server_url: config.server_url.clone(),
+        transport: trassenger_lib::transport::from_config(&config),
         signal_tx: ipc_signal_tx,
         current_interval_secs: 60,
+        certificate_warning: None,
+        peer_transports: std::collections::HashMap::new(),
     }));
 
-    // Shared sender slot for pushing events to connected TUI
-    let tui_sender: ipc::TuiEventSender = Arc::new(Mutex::new(None));
+    // Shared sender list for pushing events to connected clients
+    let tui_sender: ipc::TuiEventSender = Arc::new(Mutex::new(Vec::new()));
 
     // Start IPC listener (socket)
     ipc::start_ipc_listener(ipc_state.clone(), tui_sender.clone());
 
+    // Start loopback HTTP API (opt-in via config.api_port)
+    if let Some(port) = config.api_port {
+        api::start_api_server(port, ipc_state.clone(), tui_sender.clone());
+    }
+
     // Spawn tokio polling thread
     let state_clone = state.clone();
     let tx_clone = tx.clone();
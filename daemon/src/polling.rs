@@ -1,10 +1,15 @@
 // Background polling for the daemon
 // Polls all conversation queues, adaptive interval based on TUI connection.
+// Also watches the rendezvous queues of invites we've handed out, and our
+// public inbox when it's enabled.
+// Subscribes for push notices when the transport supports it.
 
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use trassenger_lib::{crypto, crypto::Keypair, mailbox::MailboxClient, storage};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::Instant;
+use trassenger_lib::{contact_card::{CardTrust, ContactCard}, crypto, crypto::Keypair, mailbox::{MailboxError, ServerMessage}, storage, transport::Transport};
 use crate::DaemonState;
 use crate::ipc::{IpcSignal, IpcState, TuiEventSender};
 
@@ -15,11 +20,12 @@ pub struct AdaptiveInterval {
     current_secs: u64,
     min_secs: u64,
     max_secs: u64,
+    factor: f64,
 }
 
 impl AdaptiveInterval {
-    pub fn new(min_secs: u64, max_secs: u64) -> Self {
-        Self { current_secs: min_secs, min_secs, max_secs }
+    pub fn new(min_secs: u64, max_secs: u64, factor: f64) -> Self {
+        Self { current_secs: min_secs, min_secs, max_secs, factor }
     }
 
     pub fn reset(&mut self) {
@@ -27,7 +33,8 @@ impl AdaptiveInterval {
     }
 
     pub fn increase(&mut self) {
-        self.current_secs = (self.current_secs * 2).min(self.max_secs);
+        let next = (self.current_secs as f64 * self.factor).round() as u64;
+        self.current_secs = next.clamp(self.min_secs, self.max_secs);
     }
 
     pub fn get(&self) -> u64 {
@@ -73,28 +80,59 @@ async fn polling_loop(
         }
     };
 
-    // Store keypair in IPC state so handlers can use it
-    if let Ok(mut s) = ipc_state.lock() {
-        s.keypair = Some(keypair.clone());
-    }
-
-    let config = storage::load_config().unwrap_or_else(|_| storage::Config {
-        server_url: trassenger_lib::config::DEFAULT_SERVER_URL.to_string(),
-        polling_interval_secs: 60,
-    });
-
-    let client = MailboxClient::new(config.server_url.clone());
+    // Store keypair in IPC state so handlers can use it; share its transport
+    let mut client = match ipc_state.lock() {
+        Ok(mut s) => {
+            s.keypair = Some(keypair.clone());
+            s.transport.clone()
+        }
+        Err(_) => {
+            eprintln!("[daemon] IPC state lock poisoned. Polling disabled.");
+            return;
+        }
+    };
 
-    // When TUI is connected: fast adaptive polling (5s → 60s)
-    // When TUI is not connected: slow fixed polling (60s)
+    // When TUI is connected: fast adaptive polling (polling_interval_secs → polling.max_secs)
+    // When TUI is not connected: slow polling (polling.background_secs, longer in quiet hours)
+    // When the transport pushes new-message notices: slow safety-net polling only
+    let config = storage::load_config().unwrap_or_default();
     let mut tui_connected = false;
-    let mut fast_interval = AdaptiveInterval::new(5, 60);
-    let slow_interval = 60u64;
+    let mut fast_interval = fast_interval_for(&config);
+    let mut policy = config.polling;
     let mut unread: usize = 0;
 
+    let mut push_rx: Option<UnboundedReceiver<String>> = None;
+    let mut push_queues: Vec<String> = Vec::new();
+    let mut push_supported = true;
+
     loop {
+        // (Re)subscribe when the stream dropped or the contact list changed
+        if push_supported {
+            let queue_ids = watched_queue_ids(&keypair);
+            if push_rx.is_none() || queue_ids != push_queues {
+                match client.subscribe(queue_ids.clone()).await {
+                    Ok(Some(rx)) => {
+                        if push_rx.is_none() {
+                            eprintln!("[daemon] Push delivery active");
+                        }
+                        push_rx = Some(rx);
+                        push_queues = queue_ids;
+                    }
+                    Ok(None) => {
+                        eprintln!("[daemon] Transport has no push support — using adaptive polling");
+                        push_supported = false;
+                    }
+                    Err(e) => {
+                        eprintln!("[daemon] Subscribe failed: {} — polling until next retry", e);
+                        push_rx = None;
+                    }
+                }
+            }
+        }
+
         // Poll queues — daemon owns all network I/O
-        let new_msgs = poll_all_queues(&client, &keypair, &tui_sender).await;
+        let (new_msgs, poll_error) = poll_all_queues(client.as_ref(), &keypair, &tui_sender).await;
+        crate::ipc::note_poll_result(&ipc_state, &tui_sender, poll_error.as_ref());
 
         if tui_connected {
             if new_msgs > 0 {
@@ -102,7 +140,6 @@ async fn polling_loop(
             } else {
                 fast_interval.increase();
             }
-            crate::ipc::push_polling_interval(&tui_sender, fast_interval.get());
         } else {
             if new_msgs > 0 {
                 unread += new_msgs;
@@ -111,87 +148,204 @@ async fn polling_loop(
             }
         }
 
-        let sleep_secs = if tui_connected { fast_interval.get() } else { slow_interval };
+        let mut sleep_secs = policy.jittered(if tui_connected && push_rx.is_none() {
+            fast_interval.get()
+        } else {
+            policy.background_interval_now()
+        });
+
+        // Stretch the interval while the server has asked us to back off (429/503)
+        if let Some(wait) = client.backoff_remaining() {
+            let wait_secs = wait.as_secs_f64().ceil() as u64;
+            if wait_secs > sleep_secs {
+                eprintln!("[daemon] Mailbox asked us to back off — next poll in {}s", wait_secs);
+                sleep_secs = wait_secs;
+            }
+        }
+        if tui_connected {
+            crate::ipc::push_polling_interval(&tui_sender, sleep_secs);
+        }
 
-        // Sleep for the interval, but wake immediately on any IPC signal
-        let sleep = tokio::time::sleep(Duration::from_secs(sleep_secs));
-        tokio::pin!(sleep);
+        // Sleep for the interval, fetching pushed queues as notices arrive;
+        // wake for a full poll on any IPC signal
+        let mut deadline = Instant::now() + Duration::from_secs(sleep_secs);
         loop {
             tokio::select! {
-                _ = &mut sleep => break,
+                _ = tokio::time::sleep_until(deadline) => break,
+                pushed = next_push(&mut push_rx) => match pushed {
+                    // Blocked or retired since we subscribed
+                    Some(queue_id) if !watched_queue_ids(&keypair).contains(&queue_id) => {}
+                    Some(queue_id) => {
+                        let new_msgs = poll_queue(client.as_ref(), &keypair, &queue_id, &tui_sender)
+                            .await
+                            .unwrap_or_else(|e| {
+                                eprintln!("[daemon] Poll error for {}: {}", queue_id, e);
+                                0
+                            });
+                        if new_msgs > 0 && !tui_connected {
+                            unread += new_msgs;
+                            let _ = tx.send(DaemonEvent::UnreadCount(unread));
+                            send_notification(new_msgs);
+                        }
+                    }
+                    None => {
+                        // Stream dropped: don't wait out the long safety-net interval
+                        eprintln!("[daemon] Push stream closed — falling back to polling");
+                        push_rx = None;
+                        deadline = deadline.min(Instant::now() + Duration::from_secs(fast_interval.get()));
+                    }
+                },
                 signal = signal_rx.recv() => {
+                    // Any signal triggers an immediate poll
                     match signal {
                         Some(IpcSignal::TuiConnected) => {
                             tui_connected = true;
                             unread = 0;
                             fast_interval.reset();
                             eprintln!("[daemon] TUI connected — switching to fast polling");
-                            crate::ipc::push_polling_interval(&tui_sender, fast_interval.get());
                             let _ = tx.send(DaemonEvent::UnreadCount(0));
-                            break; // Poll immediately
                         }
                         Some(IpcSignal::TuiDisconnected) => {
                             tui_connected = false;
                             eprintln!("[daemon] TUI disconnected — returning to slow polling");
-                            break; // Poll immediately
                         }
                         Some(IpcSignal::ResetPollingInterval) => {
                             fast_interval.reset();
-                            crate::ipc::push_polling_interval(&tui_sender, fast_interval.get());
-                            break; // Poll immediately
                         }
-                        None => break,
+                        // Resubscribed at the top of the loop
+                        Some(IpcSignal::QueuesChanged) => {}
+                        Some(IpcSignal::ConfigChanged) => {
+                            let config = storage::load_config().unwrap_or_default();
+                            if let Ok(s) = ipc_state.lock() {
+                                client = s.transport.clone();
+                            }
+                            fast_interval = fast_interval_for(&config);
+                            policy = config.polling.clone();
+                            // Subscribe afresh through the new transport
+                            push_rx = None;
+                            push_supported = true;
+                            eprintln!("[daemon] Config reloaded — mailbox {}", config.server_url);
+                            crate::ipc::push_config_changed(&tui_sender, config);
+                        }
+                        None => {}
                     }
+                    break;
                 }
             }
         }
     }
 }
 
+/// Adaptive interval used while a TUI is connected
+fn fast_interval_for(config: &storage::Config) -> AdaptiveInterval {
+    let min = config.polling_interval_secs.max(1);
+    AdaptiveInterval::new(min, config.polling.max_secs.max(min), config.polling.backoff_factor.max(1.0))
+}
+
+/// Wait for the next pushed queue id, or forever when there's no subscription
+async fn next_push(push_rx: &mut Option<UnboundedReceiver<String>>) -> Option<String> {
+    match push_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Queues to read: every contact's except blocked ones
+fn peer_queue_ids() -> Vec<String> {
+    storage::load_peers()
+        .map(|peers| peers.into_iter().filter(|p| !p.blocked).map(|p| p.queue_id).collect())
+        .unwrap_or_default()
+}
+
+/// Rendezvous queues of invites that haven't expired or been answered
+fn rendezvous_queue_ids() -> Vec<String> {
+    let entries = storage::load_rendezvous().unwrap_or_default();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    entries
+        .into_iter()
+        .filter(|r| now - r.created_at < trassenger_lib::config::RENDEZVOUS_TTL_SECS)
+        .map(|r| r.queue_id)
+        .collect()
+}
+
+/// Our public inbox, if enabled
+fn inbox_queue_id(keypair: &Keypair) -> Option<String> {
+    let config = storage::load_config().unwrap_or_default();
+    config.public_inbox.then(|| crypto::inbox_queue_id(&keypair.encrypt_pk))
+}
+
+/// Every queue we read: contacts', rendezvous and the public inbox
+fn watched_queue_ids(keypair: &Keypair) -> Vec<String> {
+    let mut queue_ids = peer_queue_ids();
+    queue_ids.extend(rendezvous_queue_ids());
+    queue_ids.extend(inbox_queue_id(keypair));
+    queue_ids
+}
+
+/// Poll every watched queue. Returns how many messages were new, and the
+/// last fetch error if any queue failed.
 async fn poll_all_queues(
-    client: &MailboxClient,
+    client: &dyn Transport,
     keypair: &Keypair,
     tui_sender: &TuiEventSender,
-) -> usize {
-    let peers = match storage::load_peers() {
-        Ok(p) => p,
-        Err(_) => return 0,
-    };
+) -> (usize, Option<MailboxError>) {
+    let queue_ids = watched_queue_ids(keypair);
+    if queue_ids.is_empty() {
+        return (0, None);
+    }
 
+    // One batch request where the transport supports it, bounded concurrent fetches otherwise
     let mut total = 0;
-    for peer in &peers {
-        match poll_queue(client, keypair, &peer.queue_id, tui_sender).await {
-            Ok(count) => total += count,
-            Err(e) => eprintln!("[daemon] Poll error for {}: {}", peer.queue_id, e),
+    let mut last_error = None;
+    for (queue_id, result) in client.fetch_many(&queue_ids).await {
+        match result {
+            Ok(messages) => total += process_queue(client, keypair, &queue_id, &messages, tui_sender).await,
+            Err(e) => {
+                eprintln!("[daemon] Poll error for {}: {}", queue_id, e);
+                last_error = Some(e);
+            }
         }
     }
-    total
+    (total, last_error)
 }
 
 async fn poll_queue(
-    client: &MailboxClient,
+    client: &dyn Transport,
     keypair: &Keypair,
     queue_id: &str,
     tui_sender: &TuiEventSender,
 ) -> Result<usize, String> {
-    let messages = client.fetch_messages(queue_id).await?;
-    if messages.is_empty() {
-        return Ok(0);
+    let messages = client.fetch(queue_id).await?;
+    Ok(process_queue(client, keypair, queue_id, &messages, tui_sender).await)
+}
+
+/// Decrypt, store and announce fetched messages. Returns how many were new.
+async fn process_queue(
+    client: &dyn Transport,
+    keypair: &Keypair,
+    queue_id: &str,
+    messages: &[ServerMessage],
+    tui_sender: &TuiEventSender,
+) -> usize {
+    if rendezvous_queue_ids().iter().any(|q| q == queue_id) {
+        process_rendezvous(client, keypair, queue_id, messages, tui_sender).await;
+        return 0;
+    }
+    if inbox_queue_id(keypair).as_deref() == Some(queue_id) {
+        return process_inbox(client, keypair, queue_id, messages, tui_sender).await;
     }
 
     let mut count = 0;
-    for msg in &messages {
+    for msg in messages {
         match process_message(msg, queue_id, keypair) {
-            Ok(message) => {
-                let saved = storage::init_message_db()
-                    .and_then(|conn| storage::save_message(&conn, &message))
-                    .is_ok();
-                if saved {
+            Ok(opened) => {
+                if deliver(opened.message, tui_sender) {
                     count += 1;
-                    // Push to TUI if connected
-                    crate::ipc::push_new_message(tui_sender, message);
                     // Only delete from server after successfully saving locally
-                    let _ = client.delete_message(queue_id, &msg.id).await;
+                    let _ = client.delete(queue_id, &msg.id, Some(&opened.delete_token)).await;
                 } else {
                     eprintln!("[daemon] Failed to save message {}, keeping on server for retry", msg.id);
                 }
@@ -207,14 +361,241 @@ async fn poll_queue(
             }
         }
     }
-    Ok(count)
+    count
+}
+
+/// Save an incoming message, run hooks and tell clients. False if it
+/// couldn't be saved, in which case it should stay on the server.
+fn deliver(message: storage::Message, tui_sender: &TuiEventSender) -> bool {
+    let saved = storage::init_message_db()
+        .and_then(|conn| storage::save_message(&conn, &message))
+        .is_ok();
+    if saved {
+        crate::hooks::fire(crate::hooks::Hook::NewMessage, serde_json::json!({
+            "from": crate::hooks::peer_name(&message.queue_id),
+            "message": &message,
+        }));
+        // Push to TUI if connected
+        crate::ipc::push_new_message(tui_sender, message);
+    }
+    saved
+}
+
+/// Sort messages from our public inbox. Contacts' go into their
+/// conversation, blocked senders' are dropped, and strangers' are held under
+/// the conversation they'd start while a contact request waits for approval.
+/// Returns how many contact messages were new.
+async fn process_inbox(
+    client: &dyn Transport,
+    keypair: &Keypair,
+    inbox_id: &str,
+    messages: &[ServerMessage],
+    tui_sender: &TuiEventSender,
+) -> usize {
+    let peers = storage::load_peers().unwrap_or_default();
+    let my_encrypt_pk = crypto::to_hex(&keypair.encrypt_pk);
+    let mut count = 0;
+
+    for msg in messages {
+        let opened = process_message(msg, inbox_id, keypair)
+            .and_then(|opened| Ok((inbox_message(&opened)?, opened)));
+        let ((card, mut message), opened) = match opened {
+            Ok(m) => m,
+            Err(e) if e.contains("Skipping own message") => continue,
+            Err(e) => {
+                eprintln!("[daemon] Ignoring {} in public inbox: {}", msg.id, e);
+                continue;
+            }
+        };
+        // File it under whoever actually sealed it, never the name in the payload
+        message.queue_id = match crypto::generate_conversation_queue_id(&my_encrypt_pk, &opened.sender_encrypt_pk) {
+            Ok(q) => q,
+            Err(e) => {
+                eprintln!("[daemon] Ignoring {} in public inbox: {}", msg.id, e);
+                continue;
+            }
+        };
+
+        match inbox_route(&peers, &card) {
+            InboxRoute::Drop => {}
+            InboxRoute::Deliver => {
+                if !deliver(message, tui_sender) {
+                    eprintln!("[daemon] Failed to save message {}, keeping on server for retry", msg.id);
+                    continue;
+                }
+                count += 1;
+            }
+            InboxRoute::Hold => {
+                if let Err(e) = hold_message_request(card, message, tui_sender) {
+                    eprintln!("[daemon] Failed to save message request {}, keeping on server for retry: {}", msg.id, e);
+                    continue;
+                }
+            }
+        }
+        let _ = client.delete(inbox_id, &msg.id, Some(&opened.delete_token)).await;
+    }
+    count
+}
+
+/// What to do with an inbox message, by who sent it
+#[derive(Debug, PartialEq)]
+enum InboxRoute {
+    /// A contact: into their conversation
+    Deliver,
+    /// A stranger: held behind a message request
+    Hold,
+    /// A blocked sender: dropped
+    Drop,
+}
+
+fn inbox_route(peers: &[storage::Peer], card: &ContactCard) -> InboxRoute {
+    match peers.iter().find(|p| p.encrypt_pk == card.encrypt_pk && p.sign_pk == card.sign_pk) {
+        Some(peer) if peer.blocked => InboxRoute::Drop,
+        Some(_) => InboxRoute::Deliver,
+        None => InboxRoute::Hold,
+    }
+}
+
+/// An inbox message: the sender's card and the text
+fn inbox_message(opened: &Opened) -> Result<(ContactCard, storage::Message), String> {
+    #[derive(serde::Deserialize)]
+    struct InboxContent {
+        card: ContactCard,
+        text: String,
+    }
+
+    let mut message = opened.message.clone();
+    if message.msg_type != "inbox" {
+        return Err(format!("unexpected {} message", message.msg_type));
+    }
+    let content: InboxContent = serde_json::from_str(&message.content).map_err(|e| format!("Invalid inbox message: {}", e))?;
+    check_sender_card(&content.card, opened)?;
+
+    message.content = content.text;
+    message.msg_type = "text".to_string();
+    Ok((content.card, message))
+}
+
+/// Store a stranger's message where their conversation would be, and open
+/// (or keep) a contact request for them
+fn hold_message_request(card: ContactCard, message: storage::Message, tui_sender: &TuiEventSender) -> Result<(), String> {
+    storage::init_message_db().and_then(|conn| storage::save_message(&conn, &message))?;
+
+    let mut requests = storage::load_contact_requests()?;
+    if let Some(existing) = requests.iter_mut().find(|r| r.card.encrypt_pk == card.encrypt_pk) {
+        if existing.message.is_none() {
+            existing.message = Some(message.content);
+            storage::save_contact_requests(&requests)?;
+        }
+        return Ok(());
+    }
+    let request = storage::ContactRequest {
+        id: message.id,
+        card,
+        received_at: message.timestamp,
+        message: Some(message.content),
+    };
+    requests.push(request.clone());
+    storage::save_contact_requests(&requests)?;
+
+    eprintln!("[daemon] Message request from {}", request.card.name);
+    if !crate::ipc::is_tui_connected(tui_sender) {
+        send_contact_request_notification(&request.card.name);
+    }
+    crate::ipc::push_contact_request(tui_sender, request);
+    Ok(())
+}
+
+/// Turn the first valid card posted to a rendezvous queue into a pending
+/// contact request, and retire the queue: it is single-use.
+async fn process_rendezvous(
+    client: &dyn Transport,
+    keypair: &Keypair,
+    queue_id: &str,
+    messages: &[ServerMessage],
+    tui_sender: &TuiEventSender,
+) {
+    for msg in messages {
+        let opened = process_message(msg, queue_id, keypair)
+            .and_then(|opened| Ok((contact_request(&opened)?, opened.delete_token)));
+        let (request, delete_token) = match opened {
+            Ok(r) => r,
+            Err(e) if e.contains("Skipping own message") => continue,
+            Err(e) => {
+                eprintln!("[daemon] Ignoring {} on rendezvous queue: {}", msg.id, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = save_contact_request(&request, queue_id) {
+            eprintln!("[daemon] Failed to save contact request {}, keeping on server for retry: {}", msg.id, e);
+            return;
+        }
+        let _ = client.delete(queue_id, &msg.id, Some(&delete_token)).await;
+
+        eprintln!("[daemon] Contact request from {}", request.card.name);
+        if !crate::ipc::is_tui_connected(tui_sender) {
+            send_contact_request_notification(&request.card.name);
+        }
+        crate::ipc::push_contact_request(tui_sender, request);
+        return;
+    }
 }
 
+/// A card posted to a rendezvous queue
+fn contact_request(opened: &Opened) -> Result<storage::ContactRequest, String> {
+    let message = &opened.message;
+    if message.msg_type != "contact_card" {
+        return Err(format!("unexpected {} message", message.msg_type));
+    }
+    let card: ContactCard = serde_json::from_str(&message.content).map_err(|e| format!("Invalid contact card: {}", e))?;
+    check_sender_card(&card, opened)?;
+
+    Ok(storage::ContactRequest { id: message.id.clone(), card, received_at: message.timestamp, message: None })
+}
+
+/// A card sent along with a message must be signed, and carry the keys that
+/// signed and sealed the envelope, so nobody can pass off someone else's card
+fn check_sender_card(card: &ContactCard, opened: &Opened) -> Result<(), String> {
+    if card.verify()? != CardTrust::Signed {
+        return Err("contact card is unsigned".to_string());
+    }
+    if card.sign_pk != opened.sender_sign_pk || card.encrypt_pk != opened.sender_encrypt_pk {
+        return Err("contact card doesn't match the sender's keys".to_string());
+    }
+    Ok(())
+}
+
+/// Store a request, replacing any earlier one from the same keys, and forget
+/// the rendezvous queue it answered
+fn save_contact_request(request: &storage::ContactRequest, queue_id: &str) -> Result<(), String> {
+    let mut requests = storage::load_contact_requests()?;
+    requests.retain(|r| r.card.encrypt_pk != request.card.encrypt_pk);
+    requests.push(request.clone());
+    storage::save_contact_requests(&requests)?;
+
+    let mut entries = storage::load_rendezvous()?;
+    entries.retain(|r| r.queue_id != queue_id);
+    storage::save_rendezvous(&entries)
+}
+
+/// A verified, decrypted envelope
+struct Opened {
+    message: storage::Message,
+    /// Hex deletion token that proves to the server we may delete it
+    delete_token: String,
+    /// Hex keys the envelope was signed and sealed with. Unlike the
+    /// payload's `sender_id`, these are authenticated.
+    sender_sign_pk: String,
+    sender_encrypt_pk: String,
+}
+
+/// Verify and decrypt one envelope
 fn process_message(
-    server_msg: &trassenger_lib::mailbox::ServerMessage,
+    server_msg: &ServerMessage,
     queue_id: &str,
     keypair: &Keypair,
-) -> Result<storage::Message, String> {
+) -> Result<Opened, String> {
     use base64::{Engine as _, engine::general_purpose};
 
     let full_message = general_purpose::STANDARD.decode(&server_msg.data)
@@ -252,7 +633,9 @@ fn process_message(
     let sender_id = payload["sender_id"].as_str().ok_or("Missing sender_id")?.to_string();
     let msg_type = payload["type"].as_str().unwrap_or("text").to_string();
 
-    Ok(storage::Message {
+    let delete_token = crypto::derive_delete_token(&keypair.encrypt_sk, sender_encrypt_pk, server_msg.data.as_bytes())?;
+
+    let message = storage::Message {
         id: server_msg.id.clone(),
         queue_id: queue_id.to_string(),
         sender: sender_id,
@@ -261,9 +644,26 @@ fn process_message(
         msg_type,
         status: "delivered".to_string(),
         is_outbound: false,
+    };
+    Ok(Opened {
+        message,
+        delete_token: crypto::to_hex(&delete_token),
+        sender_sign_pk: crypto::to_hex(sender_sign_pk),
+        sender_encrypt_pk: crypto::to_hex(sender_encrypt_pk),
     })
 }
 
+fn send_contact_request_notification(name: &str) {
+    #[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+    {
+        let _ = notify_rust::Notification::new()
+            .summary("Trassenger")
+            .body(&format!("{} wants to add you as a contact", name))
+            .sound_name("default")
+            .show();
+    }
+}
+
 fn send_notification(count: usize) {
     let body = if count == 1 {
         "You have 1 new message".to_string()
@@ -280,3 +680,90 @@ fn send_notification(count: usize) {
             .show();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose};
+
+    /// Seal `content` from `sender` to `recipient`, naming `sender_id` in the
+    /// payload — which, unlike the envelope keys, anyone can fill in
+    fn envelope(sender: &Keypair, recipient: &Keypair, msg_type: &str, content: &str, sender_id: &str) -> ServerMessage {
+        let payload = serde_json::json!({ "type": msg_type, "content": content, "timestamp": 1, "sender_id": sender_id });
+        let mut sealed = sender.encrypt_pk.clone();
+        sealed.extend(crypto::encrypt_message(payload.to_string().as_bytes(), &recipient.encrypt_pk, &sender.encrypt_sk).unwrap());
+        let mut data = sender.sign_pk.clone();
+        data.extend(crypto::sign_message(&sealed, &sender.sign_sk).unwrap());
+        ServerMessage { id: "m1".to_string(), timestamp: 1, data: general_purpose::STANDARD.encode(data) }
+    }
+
+    fn peer(name: &str, keys: &Keypair, blocked: bool) -> storage::Peer {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "encrypt_pk": crypto::to_hex(&keys.encrypt_pk),
+            "sign_pk": crypto::to_hex(&keys.sign_pk),
+            "queue_id": format!("{}-queue", name),
+            "blocked": blocked,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rendezvous_card_must_match_envelope_keys() {
+        let (alice, bob, mallory) = (crypto::generate_keypair(), crypto::generate_keypair(), crypto::generate_keypair());
+        let bob_id = crypto::to_hex(&bob.encrypt_pk);
+
+        let card = ContactCard::new_signed("Bob", &bob, vec![], None, false).unwrap();
+        let msg = envelope(&bob, &alice, "contact_card", &serde_json::to_string(&card).unwrap(), &bob_id);
+        let request = contact_request(&process_message(&msg, "rv", &alice).unwrap()).unwrap();
+        assert_eq!(request.card, card);
+
+        // Mallory signs a card carrying Bob's encryption key and claims his sender_id
+        let posing = Keypair { encrypt_pk: bob.encrypt_pk.clone(), ..mallory.clone() };
+        let forged = ContactCard::new_signed("Bob", &posing, vec![], None, false).unwrap();
+        let msg = envelope(&mallory, &alice, "contact_card", &serde_json::to_string(&forged).unwrap(), &bob_id);
+        assert!(contact_request(&process_message(&msg, "rv", &alice).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_inbox_card_must_match_envelope_keys() {
+        let (alice, bob, mallory) = (crypto::generate_keypair(), crypto::generate_keypair(), crypto::generate_keypair());
+        let bob_id = crypto::to_hex(&bob.encrypt_pk);
+        let content = |card: &ContactCard| serde_json::json!({ "card": card, "text": "hi" }).to_string();
+
+        let card = ContactCard::new_signed("Bob", &bob, vec![], None, true).unwrap();
+        let msg = envelope(&bob, &alice, "inbox", &content(&card), &bob_id);
+        let (sender, message) = inbox_message(&process_message(&msg, "inbox", &alice).unwrap()).unwrap();
+        assert_eq!(sender, card);
+        assert_eq!((message.content.as_str(), message.msg_type.as_str()), ("hi", "text"));
+
+        // A forged card can't get Mallory's message filed under Bob's conversation
+        let posing = Keypair { encrypt_pk: bob.encrypt_pk.clone(), ..mallory.clone() };
+        let forged = ContactCard::new_signed("Bob", &posing, vec![], None, true).unwrap();
+        let msg = envelope(&mallory, &alice, "inbox", &content(&forged), &bob_id);
+        assert!(inbox_message(&process_message(&msg, "inbox", &alice).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_inbox_routing() {
+        let (alice, bob, carol, dave) = (crypto::generate_keypair(), crypto::generate_keypair(), crypto::generate_keypair(), crypto::generate_keypair());
+        let peers = vec![peer("Bob", &bob, false), peer("Carol", &carol, true)];
+        let card = |keys: &Keypair| ContactCard::new_signed("Someone", keys, vec![], None, true).unwrap();
+
+        assert_eq!(inbox_route(&peers, &card(&bob)), InboxRoute::Deliver);
+        assert_eq!(inbox_route(&peers, &card(&carol)), InboxRoute::Drop);
+        assert_eq!(inbox_route(&peers, &card(&dave)), InboxRoute::Hold);
+        // Sharing one of a contact's keys isn't enough to reach their conversation
+        let half_bob = Keypair { encrypt_pk: dave.encrypt_pk.clone(), ..bob.clone() };
+        assert_eq!(inbox_route(&peers, &card(&half_bob)), InboxRoute::Hold);
+
+        // Each queue only takes its own kind of message
+        let dave_id = crypto::to_hex(&dave.encrypt_pk);
+        let card_json = serde_json::to_string(&card(&dave)).unwrap();
+        let msg = envelope(&dave, &alice, "contact_card", &card_json, &dave_id);
+        assert!(inbox_message(&process_message(&msg, "inbox", &alice).unwrap()).is_err());
+        let inbox_json = serde_json::json!({ "card": card(&dave), "text": "hi" }).to_string();
+        let msg = envelope(&dave, &alice, "inbox", &inbox_json, &dave_id);
+        assert!(contact_request(&process_message(&msg, "rv", &alice).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,458 @@
+// Trassenger Mailbox - reference relay server
+//
+// Implements the mailbox API the clients use (see tui/src/mailbox.rs):
+//   POST   /mailbox/{queue}        {data, meta, ttl_secs?} → {id, timestamp, success}
+//   GET    /mailbox/{queue}        → {messages: [{id, timestamp, data}]}
+//   DELETE /mailbox/{queue}/{id}   X-Delete-Token: <hex> → {success, deleted}
+//   POST   /mailbox/batch          {queues: [...]} → {queues: {queue: [{id, timestamp, data}]}}
+//   GET    /capabilities           → {features: [...]}
+//   POST   /subscribe              {queues: [...]} → server-sent events {queue_id, id}
+//
+// The server only ever sees opaque encrypted envelopes. Messages expire after
+// their TTL whether or not they were fetched. A post may carry
+// `meta.delete_hash`, the SHA-256 of a token only the conversation parties can
+// derive; deleting that message then requires the token.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+mod store;
+
+use store::{DeleteOutcome, Store, StoredMessage};
+
+const DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+/// Default (and maximum) message lifetime: 7 days
+const DEFAULT_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Default limit on the `data` field of one message
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// Default limit on unfetched messages per queue
+const DEFAULT_MAX_QUEUE_MESSAGES: usize = 1000;
+
+/// How often expired messages are purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest accepted queue or message id
+const MAX_ID_LEN: usize = 128;
+
+/// Optional protocol features this server supports (see GET /capabilities)
+const FEATURES: &[&str] = &["subscribe", "batch"];
+
+/// Most queues accepted in one batch fetch
+const MAX_BATCH_QUEUES: usize = 500;
+
+/// Interval between SSE keep-alive comments on subscriptions
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Pending new-message notifications per subscriber before it starts lagging
+const EVENT_BUFFER: usize = 1024;
+
+// ── Settings ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Settings {
+    bind: SocketAddr,
+    db_path: Option<String>,
+    ttl_secs: u64,
+    max_message_bytes: usize,
+    max_queue_messages: usize,
+}
+
+fn usage() -> &'static str {
+    "Usage: trassenger-mailbox [options]
+
+Options:
+  --bind ADDR                Listen address (default 127.0.0.1:8080)
+  --db PATH                  SQLite database file (default: in-memory)
+  --ttl SECS                 Maximum message lifetime (default 604800)
+  --max-message-bytes N      Largest accepted message (default 1048576)
+  --max-queue-messages N     Most unfetched messages per queue (default 1000)"
+}
+
+fn parse_args(args: &[String]) -> Result<Settings, String> {
+    let mut settings = Settings {
+        bind: DEFAULT_BIND.parse().expect("valid default bind address"),
+        db_path: None,
+        ttl_secs: DEFAULT_TTL_SECS,
+        max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+        max_queue_messages: DEFAULT_MAX_QUEUE_MESSAGES,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--bind" => settings.bind = value()?.parse().map_err(|e| format!("--bind: {}", e))?,
+            "--db" => settings.db_path = Some(value()?.clone()),
+            "--ttl" => settings.ttl_secs = value()?.parse().map_err(|e| format!("--ttl: {}", e))?,
+            "--max-message-bytes" => {
+                settings.max_message_bytes = value()?.parse().map_err(|e| format!("--max-message-bytes: {}", e))?
+            }
+            "--max-queue-messages" => {
+                settings.max_queue_messages = value()?.parse().map_err(|e| format!("--max-queue-messages: {}", e))?
+            }
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    Ok(settings)
+}
+
+// ── Main ──────────────────────────────────────────────────────────────────────
+
+struct Mailbox {
+    store: Mutex<Store>,
+    settings: Settings,
+    /// New-message notifications for subscribers
+    events: broadcast::Sender<Posted>,
+}
+
+/// Pushed to subscribers when a message is posted to one of their queues
+#[derive(Debug, Clone, Serialize)]
+struct Posted {
+    queue_id: String,
+    id: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", usage());
+        return;
+    }
+
+    let settings = match parse_args(&args) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, usage());
+            std::process::exit(2);
+        }
+    };
+
+    let store = match Store::open(settings.db_path.as_deref()) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[mailbox] {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let (events, _) = broadcast::channel(EVENT_BUFFER);
+    let mailbox = Arc::new(Mailbox { store: Mutex::new(store), settings: settings.clone(), events });
+
+    // Background purge of expired messages
+    let purger = mailbox.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(PURGE_INTERVAL).await;
+            if let Ok(store) = purger.store.lock() {
+                match store.purge_expired(now_millis()) {
+                    Ok(0) => {}
+                    Ok(n) => eprintln!("[mailbox] Purged {} expired message(s)", n),
+                    Err(e) => eprintln!("[mailbox] {}", e),
+                }
+            }
+        }
+    });
+
+    let make_svc = make_service_fn(move |_conn| {
+        let mailbox = mailbox.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| handle_request(req, mailbox.clone())))
+        }
+    });
+
+    let server = match Server::try_bind(&settings.bind) {
+        Ok(builder) => builder.serve(make_svc),
+        Err(e) => {
+            eprintln!("[mailbox] Failed to bind {}: {}", settings.bind, e);
+            std::process::exit(1);
+        }
+    };
+
+    eprintln!(
+        "[mailbox] Listening on http://{} ({} storage, ttl {}s)",
+        settings.bind,
+        settings.db_path.as_deref().unwrap_or("in-memory"),
+        settings.ttl_secs,
+    );
+
+    if let Err(e) = server.await {
+        eprintln!("[mailbox] Server error: {}", e);
+    }
+}
+
+// ── Routes ────────────────────────────────────────────────────────────────────
+
+async fn handle_request(req: Request<Body>, mailbox: Arc<Mailbox>) -> Result<Response<Body>, Infallible> {
+    let path = req.uri().path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let response = match (req.method(), segments.as_slice()) {
+        (&Method::POST, ["mailbox", "batch"]) => get_messages_batch(req, &mailbox).await,
+        (&Method::POST, ["mailbox", queue]) if valid_id(queue) => post_message(req, queue, &mailbox).await,
+        (&Method::GET, ["mailbox", queue]) if valid_id(queue) => get_messages(queue, &mailbox),
+        (&Method::DELETE, ["mailbox", queue, id]) if valid_id(queue) && valid_id(id) => {
+            let token = req.headers().get("x-delete-token").and_then(|v| v.to_str().ok());
+            delete_message(queue, id, token, &mailbox)
+        }
+        (&Method::GET, ["capabilities"]) => {
+            json_response(StatusCode::OK, &serde_json::json!({ "features": FEATURES }))
+        }
+        (&Method::POST, ["subscribe"]) => subscribe(req, &mailbox).await,
+        _ => json_error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    Ok(response)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PostMessageMeta {
+    #[serde(default)]
+    delete_hash: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostMessageRequest {
+    data: String,
+    #[serde(default)]
+    meta: PostMessageMeta,
+    /// Requested lifetime; capped at the server's --ttl
+    #[serde(default)]
+    ttl_secs: Option<u64>,
+}
+
+async fn post_message(req: Request<Body>, queue_id: &str, mailbox: &Mailbox) -> Response<Body> {
+    let settings = &mailbox.settings;
+
+    // Leave room for the JSON wrapper and meta around `data`
+    let body = match read_body(req.into_body(), settings.max_message_bytes + 64 * 1024).await {
+        Ok(b) => b,
+        Err(e) => return json_error(StatusCode::PAYLOAD_TOO_LARGE, &e),
+    };
+
+    let request: PostMessageRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("Invalid request: {}", e)),
+    };
+
+    if request.data.is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "Empty message");
+    }
+    if request.data.len() > settings.max_message_bytes {
+        return json_error(StatusCode::PAYLOAD_TOO_LARGE, "Message too large");
+    }
+    let delete_hash = request.meta.delete_hash.map(|h| h.to_ascii_lowercase());
+    if let Some(hash) = &delete_hash {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return json_error(StatusCode::BAD_REQUEST, "delete_hash must be a hex SHA-256 digest");
+        }
+    }
+
+    let now = now_millis();
+    let ttl_secs = request.ttl_secs.unwrap_or(settings.ttl_secs).min(settings.ttl_secs);
+    let expires_at = now + (ttl_secs as i64) * 1000;
+
+    let message = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: now,
+        data: request.data,
+    };
+
+    let store = match mailbox.store.lock() {
+        Ok(s) => s,
+        Err(_) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, "Storage unavailable"),
+    };
+
+    match store.count(queue_id, now) {
+        Ok(n) if n >= settings.max_queue_messages => {
+            // A 4xx: the relay is fine, only this queue refuses more
+            return json_error(StatusCode::CONFLICT, "Queue is full");
+        }
+        Ok(_) => {}
+        Err(e) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+
+    if let Err(e) = store.insert(queue_id, &message, expires_at, delete_hash.as_deref()) {
+        return json_error(StatusCode::INTERNAL_SERVER_ERROR, &e);
+    }
+    drop(store);
+
+    // No subscribers is not an error
+    let _ = mailbox.events.send(Posted { queue_id: queue_id.to_string(), id: message.id.clone() });
+
+    json_response(StatusCode::CREATED, &serde_json::json!({
+        "id": message.id,
+        "timestamp": message.timestamp,
+        "success": true,
+    }))
+}
+
+fn get_messages(queue_id: &str, mailbox: &Mailbox) -> Response<Body> {
+    let result = match mailbox.store.lock() {
+        Ok(store) => store.list(queue_id, now_millis()),
+        Err(_) => Err("Storage unavailable".to_string()),
+    };
+
+    match result {
+        Ok(messages) => json_response(StatusCode::OK, &serde_json::json!({ "messages": messages })),
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct QueuesRequest {
+    queues: Vec<String>,
+}
+
+/// Read a `{queues: [...]}` body, rejecting invalid ids
+async fn read_queues(req: Request<Body>) -> Result<Vec<String>, Response<Body>> {
+    let body = read_body(req.into_body(), 1024 * 1024)
+        .await
+        .map_err(|e| json_error(StatusCode::PAYLOAD_TOO_LARGE, &e))?;
+
+    let request: QueuesRequest = serde_json::from_slice(&body)
+        .map_err(|e| json_error(StatusCode::BAD_REQUEST, &format!("Invalid request: {}", e)))?;
+
+    if let Some(bad) = request.queues.iter().find(|q| !valid_id(q)) {
+        return Err(json_error(StatusCode::BAD_REQUEST, &format!("Invalid queue id: {}", bad)));
+    }
+    Ok(request.queues)
+}
+
+/// Fetch several queues in one round trip
+async fn get_messages_batch(req: Request<Body>, mailbox: &Mailbox) -> Response<Body> {
+    let queues = match read_queues(req).await {
+        Ok(q) => q,
+        Err(response) => return response,
+    };
+    if queues.len() > MAX_BATCH_QUEUES {
+        return json_error(StatusCode::PAYLOAD_TOO_LARGE, &format!("At most {} queues per batch", MAX_BATCH_QUEUES));
+    }
+
+    let store = match mailbox.store.lock() {
+        Ok(s) => s,
+        Err(_) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, "Storage unavailable"),
+    };
+
+    let now = now_millis();
+    let mut result = serde_json::Map::new();
+    for queue_id in queues {
+        match store.list(&queue_id, now) {
+            Ok(messages) => {
+                result.insert(queue_id, serde_json::json!(messages));
+            }
+            Err(e) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+        }
+    }
+
+    json_response(StatusCode::OK, &serde_json::json!({ "queues": result }))
+}
+
+fn delete_message(queue_id: &str, id: &str, token: Option<&str>, mailbox: &Mailbox) -> Response<Body> {
+    let result = match mailbox.store.lock() {
+        Ok(store) => store.delete(queue_id, id, token),
+        Err(_) => Err("Storage unavailable".to_string()),
+    };
+
+    match result {
+        Ok(DeleteOutcome::Deleted) => json_response(StatusCode::OK, &serde_json::json!({ "success": true, "deleted": id })),
+        Ok(DeleteOutcome::NotFound) => json_error(StatusCode::NOT_FOUND, "Message not found"),
+        Ok(DeleteOutcome::Forbidden) => json_error(StatusCode::FORBIDDEN, "Missing or invalid delete token"),
+        Err(e) => json_error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+}
+
+/// Stream `{queue_id, id}` events for new messages on the requested queues.
+/// Clients still fetch the message itself with GET /mailbox/{queue}.
+async fn subscribe(req: Request<Body>, mailbox: &Mailbox) -> Response<Body> {
+    let queues: HashSet<String> = match read_queues(req).await {
+        Ok(q) => q.into_iter().collect(),
+        Err(response) => return response,
+    };
+
+    let mut event_rx = mailbox.events.subscribe();
+    let (mut body_tx, body) = Body::channel();
+
+    tokio::spawn(async move {
+        loop {
+            let chunk = tokio::select! {
+                ev = event_rx.recv() => match ev {
+                    Ok(posted) if queues.contains(&posted.queue_id) => {
+                        match serde_json::to_string(&posted) {
+                            Ok(json) => format!("data: {}\n\n", json),
+                            Err(_) => continue,
+                        }
+                    }
+                    Ok(_) => continue,
+                    // Missed notifications are picked up by the client's safety-net poll
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = tokio::time::sleep(SSE_KEEPALIVE) => ": keep-alive\n\n".to_string(),
+            };
+
+            if body_tx.send_data(Bytes::from(chunk)).await.is_err() {
+                break; // Client went away
+            }
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, "text/event-stream")
+        .header(hyper::header::CACHE_CONTROL, "no-cache")
+        .body(body)
+        .expect("valid SSE response")
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Queue and message ids: non-empty, bounded, URL-safe
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| format!("Read body: {}", e))?;
+        if buf.len() + chunk.len() > limit {
+            return Err("Request body too large".to_string());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf)
+}
+
+fn json_response<T: serde::Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let json = serde_json::to_string(value).unwrap_or_else(|_| "null".to_string());
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json))
+        .expect("valid JSON response")
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, &serde_json::json!({ "success": false, "error": message }))
+}
@@ -0,0 +1,185 @@
+// Message storage for the mailbox server
+// SQLite either on disk (--db PATH) or in memory (default). Messages carry an
+// absolute expiry; expired rows are hidden from reads and purged periodically.
+// Messages posted with a deletion commitment can only be deleted by whoever
+// presents its preimage.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// A stored envelope, as returned by `GET /mailbox/{queue}`
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredMessage {
+    pub id: String,
+    pub timestamp: i64,
+    pub data: String,
+}
+
+/// Result of a delete request
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    Deleted,
+    NotFound,
+    /// The message has a commitment and the token was missing or wrong
+    Forbidden,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open a database file, or an in-memory store when `path` is None
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        let conn = match path {
+            Some(p) => Connection::open(p),
+            None => Connection::open_in_memory(),
+        }
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS messages (
+                id TEXT PRIMARY KEY,
+                queue_id TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                delete_hash TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_messages_queue ON messages(queue_id, timestamp);",
+        )
+        .map_err(|e| format!("Failed to create tables: {}", e))?;
+
+        // Databases created before deletion commitments lack the column
+        let _ = conn.execute("ALTER TABLE messages ADD COLUMN delete_hash TEXT", []);
+
+        Ok(Self { conn })
+    }
+
+    /// Store a message. `delete_hash` is the hex SHA-256 of its deletion token, if any.
+    pub fn insert(&self, queue_id: &str, msg: &StoredMessage, expires_at: i64, delete_hash: Option<&str>) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO messages (id, queue_id, timestamp, expires_at, data, delete_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![msg.id, queue_id, msg.timestamp, expires_at, msg.data, delete_hash],
+            )
+            .map_err(|e| format!("Failed to store message: {}", e))?;
+        Ok(())
+    }
+
+    /// Unexpired messages in a queue, oldest first
+    pub fn list(&self, queue_id: &str, now: i64) -> Result<Vec<StoredMessage>, String> {
+        let mut stmt = self.conn
+            .prepare(
+                "SELECT id, timestamp, data FROM messages
+                 WHERE queue_id = ?1 AND expires_at > ?2 ORDER BY timestamp ASC",
+            )
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![queue_id, now], |row| {
+                Ok(StoredMessage { id: row.get(0)?, timestamp: row.get(1)?, data: row.get(2)? })
+            })
+            .map_err(|e| format!("Failed to query messages: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read messages: {}", e))
+    }
+
+    /// Number of unexpired messages waiting in a queue
+    pub fn count(&self, queue_id: &str, now: i64) -> Result<usize, String> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages WHERE queue_id = ?1 AND expires_at > ?2",
+                params![queue_id, now],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n as usize)
+            .map_err(|e| format!("Failed to count messages: {}", e))
+    }
+
+    /// Delete one message, checking `token` (hex) against its commitment.
+    /// Messages posted without a commitment can be deleted by anyone.
+    pub fn delete(&self, queue_id: &str, id: &str, token: Option<&str>) -> Result<DeleteOutcome, String> {
+        let commitment: Option<Option<String>> = self.conn
+            .query_row(
+                "SELECT delete_hash FROM messages WHERE queue_id = ?1 AND id = ?2",
+                params![queue_id, id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to look up message: {}", e))?;
+
+        let authorized = match commitment {
+            None => return Ok(DeleteOutcome::NotFound),
+            Some(None) => true,
+            Some(Some(hash)) => token.is_some_and(|t| matches_commitment(t, &hash)),
+        };
+        if !authorized {
+            return Ok(DeleteOutcome::Forbidden);
+        }
+
+        self.conn
+            .execute("DELETE FROM messages WHERE queue_id = ?1 AND id = ?2", params![queue_id, id])
+            .map_err(|e| format!("Failed to delete message: {}", e))?;
+        Ok(DeleteOutcome::Deleted)
+    }
+
+    /// Drop every message whose expiry has passed. Returns how many were removed.
+    pub fn purge_expired(&self, now: i64) -> Result<usize, String> {
+        self.conn
+            .execute("DELETE FROM messages WHERE expires_at <= ?1", params![now])
+            .map_err(|e| format!("Failed to purge messages: {}", e))
+    }
+}
+
+/// Whether hex `token` hashes to hex `hash` (compared in constant time)
+fn matches_commitment(token: &str, hash: &str) -> bool {
+    let Ok(token) = hex::decode(token) else { return false };
+    let computed = hex::encode(Sha256::digest(&token));
+    computed.len() == hash.len()
+        && computed.bytes().zip(hash.to_ascii_lowercase().bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: &str, timestamp: i64) -> StoredMessage {
+        StoredMessage { id: id.to_string(), timestamp, data: "ZGF0YQ==".to_string() }
+    }
+
+    #[test]
+    fn test_list_delete_and_expiry() {
+        let store = Store::open(None).unwrap();
+        store.insert("q1", &msg("b", 20), 1_000, None).unwrap();
+        store.insert("q1", &msg("a", 10), 100, None).unwrap();
+        store.insert("q2", &msg("c", 30), 1_000, None).unwrap();
+
+        let ids: Vec<String> = store.list("q1", 50).unwrap().into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        // Expired messages are hidden before they're purged
+        assert_eq!(store.count("q1", 500).unwrap(), 1);
+        assert_eq!(store.purge_expired(500).unwrap(), 1);
+
+        assert_eq!(store.delete("q1", "b", None).unwrap(), DeleteOutcome::Deleted);
+        assert_eq!(store.delete("q1", "b", None).unwrap(), DeleteOutcome::NotFound);
+        assert_eq!(store.delete("q1", "c", None).unwrap(), DeleteOutcome::NotFound, "delete is scoped to the queue");
+        assert_eq!(store.list("q2", 50).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_delete_requires_token_preimage() {
+        let store = Store::open(None).unwrap();
+        let token = hex::encode([7u8; 32]);
+        let hash = hex::encode(Sha256::digest([7u8; 32]));
+        store.insert("q1", &msg("a", 10), 1_000, Some(&hash)).unwrap();
+
+        assert_eq!(store.delete("q1", "a", None).unwrap(), DeleteOutcome::Forbidden);
+        assert_eq!(store.delete("q1", "a", Some(&hash)).unwrap(), DeleteOutcome::Forbidden);
+        assert_eq!(store.delete("q1", "a", Some(&token)).unwrap(), DeleteOutcome::Deleted);
+    }
+}
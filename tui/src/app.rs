@@ -1,5 +1,6 @@
 use crate::ipc::{DaemonClient, DaemonEvent};
-use crate::storage::{Config, Message, Peer};
+use crate::polling_policy::{PollingPolicy, QuietHours};
+use crate::storage::{Config, ContactRequest, Message, Peer};
 use crate::event::AppEvent;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
@@ -8,11 +9,28 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 pub enum MenuState {
     Closed,
     Contacts,
+    /// Keys, safety number and verification for the selected contact
+    ContactDetails,
     ImportContact,
     ExportContact,
+    /// Cards that answered our invites, waiting for approval
+    Requests,
     Settings,
 }
 
+/// Contact change started from the Contacts view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactAction {
+    Rename,
+    Notes,
+    /// Waiting for confirmation
+    Delete,
+}
+
+/// Settings rows: text fields first, then the autostart and public inbox toggles
+pub const SETTINGS_AUTOSTART_FIELD: usize = 7;
+pub const SETTINGS_INBOX_FIELD: usize = 8;
+
 /// Input mode for text editing
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
@@ -47,16 +65,37 @@ pub struct App {
     pub contact_import_input: String,
     pub contact_export_name: String,
     pub contact_export_json: String,
+    pub contact_export_invite: String,
+    pub contact_export_path: Option<String>,
+
+    // Contact management (Contacts view)
+    pub contact_action: Option<ContactAction>,
+    pub contact_edit_input: String,
+    /// Queue of the contact `contact_action` applies to
+    contact_action_queue: String,
+    /// Safety number shown in ContactDetails: (queue_id, digit groups)
+    pub safety_number: Option<(String, Vec<String>)>,
+
+    // Contact requests (Requests view)
+    pub contact_requests: Vec<ContactRequest>,
+    pub selected_request_index: usize,
 
     // Settings (cached for display)
     pub settings_selected_field: usize,
     pub settings_server_url: String,
     pub settings_polling_interval: String,
+    pub settings_max_interval: String,
+    pub settings_background_interval: String,
+    pub settings_backoff_factor: String,
+    pub settings_jitter: String,
+    pub settings_quiet_hours: String,
     pub settings_autostart_enabled: bool,
 
     // Status
     pub status_message: String,
     pub current_polling_interval: u64,
+    /// Set while the mailbox's certificate fails its pin
+    pub certificate_warning: Option<String>,
 
     pub chat_scroll_offset: usize,
     pub should_quit: bool,
@@ -82,10 +121,7 @@ impl App {
         };
 
         // Load config (from disk directly - TUI still reads config for display)
-        let config = crate::storage::load_config().unwrap_or_else(|_| Config {
-            server_url: crate::config::DEFAULT_SERVER_URL.to_string(),
-            polling_interval_secs: crate::config::DEFAULT_POLLING_INTERVAL,
-        });
+        let config = crate::storage::load_config().unwrap_or_default();
 
         let mut app = Self {
             daemon,
@@ -105,20 +141,39 @@ impl App {
             contact_import_input: String::new(),
             contact_export_name: String::new(),
             contact_export_json: String::new(),
+            contact_export_invite: String::new(),
+            contact_export_path: None,
+
+            contact_action: None,
+            contact_edit_input: String::new(),
+            contact_action_queue: String::new(),
+            safety_number: None,
+
+            contact_requests: Vec::new(),
+            selected_request_index: 0,
 
             settings_selected_field: 0,
-            settings_server_url: config.server_url.clone(),
-            settings_polling_interval: config.polling_interval_secs.to_string(),
+            settings_server_url: String::new(),
+            settings_polling_interval: String::new(),
+            settings_max_interval: String::new(),
+            settings_background_interval: String::new(),
+            settings_backoff_factor: String::new(),
+            settings_jitter: String::new(),
+            settings_quiet_hours: String::new(),
             settings_autostart_enabled: check_autostart_enabled(),
 
             status_message: String::new(),
             current_polling_interval: config.polling_interval_secs,
+            certificate_warning: None,
 
             chat_scroll_offset: 0,
             should_quit: false,
             keyboard_enhancements_supported: false,
         };
 
+        app.reset_settings_fields();
+        app.daemon.load_contact_requests();
+
         // Load messages for first peer
         if !app.peers.is_empty() {
             app.load_messages_for_selected_peer();
@@ -143,29 +198,90 @@ impl App {
                 }
             }
             DaemonEvent::Peers { peers } => {
+                // Keep the same contact selected; if it was deleted, move to a neighbour
+                let selected = self.peers.get(self.selected_peer_index).map(|p| p.queue_id.clone());
                 self.peers = peers;
+                match selected.and_then(|q| self.peers.iter().position(|p| p.queue_id == q)) {
+                    Some(index) => self.selected_peer_index = index,
+                    None => {
+                        self.selected_peer_index = self.selected_peer_index.min(self.peers.len().saturating_sub(1));
+                        self.messages.clear();
+                        self.load_messages_for_selected_peer();
+                    }
+                }
             }
-            DaemonEvent::ContactImported { peer } => {
+            DaemonEvent::ContactImported { peer, warning } => {
                 if !self.peers.iter().any(|p| p.encrypt_pk == peer.encrypt_pk) {
                     self.peers.push(peer.clone());
                 }
-                self.status_message = format!("Contact '{}' imported", peer.name);
+                // A message request brings history with it
+                self.load_messages_for_selected_peer();
+                self.status_message = match warning {
+                    Some(warning) => format!("Contact '{}' imported - ⚠ {}", peer.name, warning),
+                    None => format!("Contact '{}' imported", peer.name),
+                };
+                self.contact_import_input.clear();
+                // Stay put when accepting from a list of several requests
+                if self.menu_state != MenuState::Requests {
+                    self.menu_state = MenuState::Closed;
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            DaemonEvent::ContactKeyChanged { peer } => {
+                // The queue moved with the key, so refetch the list
+                self.daemon.load_peers();
+                self.status_message = format!("⚠ {}'s key has changed - verify their safety number again", peer.name);
                 self.contact_import_input.clear();
                 self.menu_state = MenuState::Closed;
                 self.input_mode = InputMode::Normal;
             }
-            DaemonEvent::ContactExported { json } => {
+            DaemonEvent::ContactExported { json, invite, saved_to } => {
                 self.contact_export_json = json;
-                let name = self.contact_export_name.trim().replace(' ', "-");
-                self.status_message = format!("Saved to Downloads: contact-{}.json", name);
+                self.contact_export_invite = invite;
+                self.status_message = match &saved_to {
+                    Some(path) => format!("Saved to {}", path),
+                    None => "No Downloads folder; share the invite code instead".to_string(),
+                };
+                self.contact_export_path = saved_to;
                 self.input_mode = InputMode::Normal;
             }
+            DaemonEvent::SafetyNumber { queue_id, groups } => {
+                self.safety_number = Some((queue_id, groups));
+            }
+            DaemonEvent::ContactRequests { requests } => {
+                self.contact_requests = requests;
+                self.selected_request_index = self.selected_request_index.min(self.contact_requests.len().saturating_sub(1));
+            }
+            DaemonEvent::ContactRequest { request } => {
+                self.status_message = if request.message.is_some() {
+                    format!("Message request from {} - /requests to review", request.card.name)
+                } else {
+                    format!("{} answered your invite - /requests to review", request.card.name)
+                };
+                self.contact_requests.retain(|r| r.card.encrypt_pk != request.card.encrypt_pk);
+                self.contact_requests.push(request);
+            }
             DaemonEvent::MessageSent => {
                 self.load_messages_for_selected_peer();
             }
             DaemonEvent::PollingInterval { secs } => {
                 self.current_polling_interval = secs;
             }
+            DaemonEvent::Status { polling_interval_secs, .. } => {
+                self.current_polling_interval = polling_interval_secs;
+            }
+            DaemonEvent::CertificateWarning { message } => {
+                self.certificate_warning = message;
+            }
+            DaemonEvent::ConfigChanged { config } => {
+                // Don't clobber fields the user is still typing into
+                let editing = self.menu_state == MenuState::Settings && self.input_mode == InputMode::Editing;
+                self.config = config;
+                if !editing {
+                    self.reset_settings_fields();
+                }
+                self.status_message = "Settings applied".to_string();
+            }
             DaemonEvent::Error { message } => {
                 self.status_message = format!("Error: {}", message);
                 self.input_mode = InputMode::Normal;
@@ -204,7 +320,10 @@ impl App {
     fn handle_paste(&mut self, text: String) {
         if self.menu_state == MenuState::ImportContact {
             let trimmed = text.trim();
-            if trimmed.ends_with(".json") || trimmed.starts_with("file://") {
+            if crate::contact_card::is_invite(trimmed) {
+                self.contact_import_input = trimmed.to_string();
+                self.status_message = "Invite code pasted - press Enter to import".to_string();
+            } else if trimmed.ends_with(".json") || trimmed.starts_with("file://") {
                 let path = trimmed.trim_start_matches("file://");
                 self.contact_import_input = path.to_string();
                 self.status_message = "File path pasted - press Enter to import".to_string();
@@ -240,7 +359,16 @@ impl App {
     }
 
     fn handle_key_normal(&mut self, key: KeyEvent) {
+        if self.contact_action == Some(ContactAction::Delete) {
+            self.confirm_delete_contact(key.code);
+            return;
+        }
+
         match key.code {
+            KeyCode::Esc if self.menu_state == MenuState::ContactDetails => {
+                self.menu_state = MenuState::Contacts;
+                self.status_message.clear();
+            }
             KeyCode::Esc => {
                 self.menu_state = MenuState::Closed;
                 self.status_message.clear();
@@ -254,25 +382,42 @@ impl App {
             }
             KeyCode::Up if self.menu_state == MenuState::Contacts => self.handle_up(),
             KeyCode::Down if self.menu_state == MenuState::Contacts => self.handle_down(),
+            KeyCode::Char('v') if self.menu_state == MenuState::ContactDetails => self.toggle_verified(),
+            KeyCode::Up if self.menu_state == MenuState::Requests => {
+                self.selected_request_index = self.selected_request_index.saturating_sub(1);
+            }
+            KeyCode::Down if self.menu_state == MenuState::Requests => {
+                self.selected_request_index = (self.selected_request_index + 1).min(self.contact_requests.len().saturating_sub(1));
+            }
+            KeyCode::Char(c @ ('a' | 'x' | 'b')) if self.menu_state == MenuState::Requests => self.answer_contact_request(c),
+            KeyCode::Char('c') if self.menu_state == MenuState::ExportContact
+                && !self.contact_export_invite.is_empty() => self.copy_invite(),
             KeyCode::Up if self.menu_state == MenuState::Settings => {
-                if self.settings_selected_field > 0 { self.settings_selected_field -= 1; }
+                self.settings_selected_field = self.settings_selected_field.saturating_sub(1);
             }
             KeyCode::Down if self.menu_state == MenuState::Settings => {
-                if self.settings_selected_field < 2 { self.settings_selected_field += 1; }
+                self.settings_selected_field = (self.settings_selected_field + 1).min(SETTINGS_INBOX_FIELD);
+            }
+            KeyCode::Enter if self.menu_state == MenuState::Settings => {
+                if self.settings_selected_field >= SETTINGS_AUTOSTART_FIELD {
+                    self.submit_settings();
+                } else {
+                    self.input_mode = InputMode::Editing;
+                    self.status_message = "Editing - Enter to save, Esc to cancel".to_string();
+                }
             }
-            KeyCode::Enter if self.menu_state == MenuState::Settings => self.submit_settings(),
             KeyCode::Up if self.menu_state == MenuState::Closed => {
                 self.chat_scroll_offset = self.chat_scroll_offset.saturating_add(1);
             }
             KeyCode::Down if self.menu_state == MenuState::Closed => {
                 self.chat_scroll_offset = self.chat_scroll_offset.saturating_sub(1);
             }
-            KeyCode::Enter if self.menu_state == MenuState::Contacts => {
-                if !self.peers.is_empty() && self.selected_peer_index < self.peers.len() {
-                    self.menu_state = MenuState::Closed;
-                    self.load_messages_for_selected_peer();
-                }
+            KeyCode::Enter if self.menu_state == MenuState::Contacts
+                && self.selected_peer_index < self.peers.len() => {
+                self.menu_state = MenuState::Closed;
+                self.load_messages_for_selected_peer();
             }
+            KeyCode::Char(c) if self.menu_state == MenuState::Contacts => self.start_contact_action(c),
             KeyCode::Char(c) if self.menu_state == MenuState::Closed => {
                 if !self.peers.is_empty() {
                     self.input_mode = InputMode::Editing;
@@ -332,6 +477,10 @@ impl App {
 
         match key.code {
             KeyCode::Esc => {
+                if self.menu_state == MenuState::Settings {
+                    self.reset_settings_fields();
+                }
+                self.contact_action = None;
                 self.input_mode = InputMode::Normal;
                 self.message_input.clear();
                 self.input_cursor = 0;
@@ -372,6 +521,7 @@ impl App {
         if self.selected_peer_index > 0 {
             self.selected_peer_index -= 1;
             self.load_messages_for_selected_peer();
+            self.load_safety_number();
         }
     }
 
@@ -379,6 +529,7 @@ impl App {
         if !self.peers.is_empty() && self.selected_peer_index < self.peers.len() - 1 {
             self.selected_peer_index += 1;
             self.load_messages_for_selected_peer();
+            self.load_safety_number();
         }
     }
 
@@ -395,7 +546,8 @@ impl App {
             MenuState::ImportContact => self.import_contact(),
             MenuState::ExportContact => self.export_contact(),
             MenuState::Settings => self.submit_settings(),
-            _ => { self.input_mode = InputMode::Normal; }
+            MenuState::Contacts | MenuState::ContactDetails => self.submit_contact_edit(),
+            MenuState::Requests => {}
         }
     }
 
@@ -416,9 +568,17 @@ impl App {
                 self.menu_state = MenuState::ExportContact;
                 self.contact_export_name.clear();
                 self.contact_export_json.clear();
+                self.contact_export_invite.clear();
+                self.contact_export_path = None;
                 self.clear_message_input();
                 self.input_mode = InputMode::Editing;
             }
+            "/requests" | "/r" => {
+                self.menu_state = MenuState::Requests;
+                self.daemon.load_contact_requests();
+                self.clear_message_input();
+                self.input_mode = InputMode::Normal;
+            }
             "/settings" | "/s" => {
                 self.menu_state = MenuState::Settings;
                 self.clear_message_input();
@@ -477,8 +637,8 @@ impl App {
             return;
         }
 
-        // Try to parse as JSON or file path
-        let json_str = if input.starts_with('{') {
+        // Card JSON and invite codes go to the daemon as-is; anything else is a file path
+        let json_str = if input.starts_with('{') || crate::contact_card::is_invite(&input) {
             input.clone()
         } else {
             let file_path = if input.starts_with('/') || input.starts_with('~') {
@@ -523,8 +683,177 @@ impl App {
         self.input_mode = InputMode::Normal;
     }
 
+    /// Contacts view keys: i details, r rename, n notes, b block/unblock, d delete
+    fn start_contact_action(&mut self, key: char) {
+        let Some(peer) = self.peers.get(self.selected_peer_index).cloned() else { return };
+        self.contact_action_queue = peer.queue_id.clone();
+
+        match key {
+            'i' => {
+                self.menu_state = MenuState::ContactDetails;
+                self.load_safety_number();
+            }
+            'r' => {
+                self.contact_action = Some(ContactAction::Rename);
+                self.contact_edit_input = peer.name;
+                self.input_mode = InputMode::Editing;
+                self.status_message = "Rename - Enter to save, Esc to cancel".to_string();
+            }
+            'n' => {
+                self.contact_action = Some(ContactAction::Notes);
+                self.contact_edit_input = peer.notes;
+                self.input_mode = InputMode::Editing;
+                self.status_message = "Notes - Enter to save, Esc to cancel".to_string();
+            }
+            'b' => {
+                self.daemon.block_contact(&peer.queue_id, !peer.blocked);
+                self.status_message = if peer.blocked {
+                    format!("Unblocked {}", peer.name)
+                } else {
+                    format!("Blocked {} - their messages are no longer fetched", peer.name)
+                };
+            }
+            'd' => {
+                self.contact_action = Some(ContactAction::Delete);
+                self.status_message = format!("Delete {}? y = delete, p = delete and purge history, Esc = cancel", peer.name);
+            }
+            _ => {}
+        }
+    }
+
+    /// Accept (a), reject (x) or block (b) the selected contact request
+    fn answer_contact_request(&mut self, key: char) {
+        let Some(request) = self.contact_requests.get(self.selected_request_index) else { return };
+        match key {
+            'a' => {
+                self.daemon.accept_contact_request(&request.id);
+                self.status_message = format!("Adding {}...", request.card.name);
+            }
+            'b' => {
+                self.daemon.reject_contact_request(&request.id, true);
+                self.status_message = format!("Blocked {} - unblock them from /contacts", request.card.name);
+            }
+            _ => {
+                self.daemon.reject_contact_request(&request.id, false);
+                self.status_message = format!("Rejected {}", request.card.name);
+            }
+        }
+    }
+
+    /// Ask the daemon for the selected contact's safety number (ContactDetails only)
+    fn load_safety_number(&mut self) {
+        if self.menu_state != MenuState::ContactDetails {
+            return;
+        }
+        if let Some(peer) = self.peers.get(self.selected_peer_index) {
+            self.daemon.get_safety_number(&peer.queue_id);
+        }
+    }
+
+    fn toggle_verified(&mut self) {
+        let Some(peer) = self.peers.get(self.selected_peer_index) else { return };
+        self.daemon.verify_contact(&peer.queue_id, !peer.verified);
+        self.status_message = if peer.verified {
+            format!("{} marked as not verified", peer.name)
+        } else {
+            format!("{} marked as verified", peer.name)
+        };
+    }
+
+    fn confirm_delete_contact(&mut self, key: KeyCode) {
+        self.contact_action = None;
+        let purge_history = match key {
+            KeyCode::Char('y') => false,
+            KeyCode::Char('p') => true,
+            _ => {
+                self.status_message = "Delete cancelled".to_string();
+                return;
+            }
+        };
+        self.daemon.delete_contact(&self.contact_action_queue, purge_history);
+        self.status_message = if purge_history {
+            "Contact and history deleted".to_string()
+        } else {
+            "Contact deleted".to_string()
+        };
+    }
+
+    fn submit_contact_edit(&mut self) {
+        let input = self.contact_edit_input.trim().to_string();
+        match self.contact_action.take() {
+            Some(ContactAction::Rename) => {
+                if input.is_empty() {
+                    self.status_message = "Name cannot be empty".to_string();
+                    self.contact_action = Some(ContactAction::Rename);
+                    return;
+                }
+                self.daemon.rename_contact(&self.contact_action_queue, &input);
+                self.status_message = format!("Renamed to {}", input);
+            }
+            Some(ContactAction::Notes) => {
+                self.daemon.set_contact_notes(&self.contact_action_queue, &input);
+                self.status_message = "Notes saved".to_string();
+            }
+            _ => {}
+        }
+        self.contact_edit_input.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn copy_invite(&mut self) {
+        let copied = arboard::Clipboard::new().and_then(|mut c| c.set_text(self.contact_export_invite.clone()));
+        self.status_message = match copied {
+            Ok(()) => "Invite code copied to clipboard".to_string(),
+            Err(e) => format!("Couldn't copy invite code: {}", e),
+        };
+    }
+
+    /// Refill the Settings fields from the current config
+    fn reset_settings_fields(&mut self) {
+        let polling = &self.config.polling;
+        self.settings_server_url = self.config.server_url.clone();
+        self.settings_polling_interval = self.config.polling_interval_secs.to_string();
+        self.settings_max_interval = polling.max_secs.to_string();
+        self.settings_background_interval = polling.background_secs.to_string();
+        self.settings_backoff_factor = polling.backoff_factor.to_string();
+        self.settings_jitter = polling.jitter.to_string();
+        self.settings_quiet_hours = polling.quiet_hours.as_ref().map_or_else(|| "off".to_string(), |q| q.to_string());
+    }
+
+    /// The Settings text field under the cursor
+    fn selected_settings_field(&mut self) -> Option<&mut String> {
+        match self.settings_selected_field {
+            0 => Some(&mut self.settings_server_url),
+            1 => Some(&mut self.settings_polling_interval),
+            2 => Some(&mut self.settings_max_interval),
+            3 => Some(&mut self.settings_background_interval),
+            4 => Some(&mut self.settings_backoff_factor),
+            5 => Some(&mut self.settings_jitter),
+            6 => Some(&mut self.settings_quiet_hours),
+            _ => None,
+        }
+    }
+
+    /// Parse the polling fields of the Settings view
+    fn parse_polling_policy(&self) -> Result<PollingPolicy, String> {
+        let secs = |value: &str, name: &str| {
+            value.trim().trim_end_matches('s').parse::<u64>()
+                .map_err(|_| format!("Invalid {} (must be a whole number of seconds)", name))
+        };
+        let fraction = |value: &str, name: &str| {
+            value.trim().parse::<f64>().map_err(|_| format!("Invalid {} (must be a number)", name))
+        };
+        Ok(PollingPolicy {
+            max_secs: secs(&self.settings_max_interval, "max interval")?,
+            background_secs: secs(&self.settings_background_interval, "background interval")?,
+            backoff_factor: fraction(&self.settings_backoff_factor, "backoff factor")?,
+            jitter: fraction(&self.settings_jitter, "jitter")?,
+            quiet_hours: QuietHours::parse(&self.settings_quiet_hours)?,
+        })
+    }
+
     fn submit_settings(&mut self) {
-        if self.settings_selected_field == 2 {
+        if self.settings_selected_field == SETTINGS_AUTOSTART_FIELD {
             let now_enabled = toggle_autostart();
             self.settings_autostart_enabled = check_autostart_enabled();
             if now_enabled {
@@ -534,6 +863,17 @@ impl App {
             }
             return;
         }
+        if self.settings_selected_field == SETTINGS_INBOX_FIELD {
+            // Applies the saved settings with the toggle flipped
+            let config = &self.config;
+            self.daemon.update_config(&config.server_url, config.polling_interval_secs, &config.polling, !config.public_inbox);
+            self.status_message = if config.public_inbox {
+                "Public inbox off".to_string()
+            } else {
+                "Public inbox on - export your card again so others can use it".to_string()
+            };
+            return;
+        }
 
         let new_url = self.settings_server_url.trim().to_string();
         let new_interval_str = self.settings_polling_interval.trim().to_string();
@@ -553,32 +893,39 @@ impl App {
             }
         };
 
+        let policy = match self.parse_polling_policy().and_then(|p| p.validate(new_interval).map(|_| p)) {
+            Ok(policy) => policy,
+            Err(e) => {
+                self.status_message = e;
+                self.input_mode = InputMode::Normal;
+                return;
+            }
+        };
+
         self.config.server_url = new_url.clone();
         self.config.polling_interval_secs = new_interval;
+        self.config.polling = policy.clone();
 
-        // Send to daemon
-        self.daemon.update_config(&new_url, new_interval);
-        self.status_message = "Settings saved (restart daemon to apply)".to_string();
+        // Send to daemon — applied live, confirmed by ConfigChanged
+        self.daemon.update_config(&new_url, new_interval, &policy, self.config.public_inbox);
+        self.status_message = "Saving settings...".to_string();
         self.input_mode = InputMode::Normal;
     }
 
     fn handle_backspace(&mut self) {
         match self.menu_state {
-            MenuState::Closed => {
-                if self.input_cursor > 0 {
-                    let byte_pos = char_to_byte_index(&self.message_input, self.input_cursor - 1);
-                    let next_byte = char_to_byte_index(&self.message_input, self.input_cursor);
-                    self.message_input.drain(byte_pos..next_byte);
-                    self.input_cursor -= 1;
-                }
+            MenuState::Closed if self.input_cursor > 0 => {
+                let byte_pos = char_to_byte_index(&self.message_input, self.input_cursor - 1);
+                let next_byte = char_to_byte_index(&self.message_input, self.input_cursor);
+                self.message_input.drain(byte_pos..next_byte);
+                self.input_cursor -= 1;
             }
             MenuState::ImportContact => { self.contact_import_input.pop(); }
             MenuState::ExportContact => { self.contact_export_name.pop(); }
+            MenuState::Contacts => { self.contact_edit_input.pop(); }
             MenuState::Settings => {
-                match self.settings_selected_field {
-                    0 => { self.settings_server_url.pop(); }
-                    1 => { self.settings_polling_interval.pop(); }
-                    _ => {}
+                if let Some(field) = self.selected_settings_field() {
+                    field.pop();
                 }
             }
             _ => {}
@@ -605,14 +952,13 @@ impl App {
             }
             MenuState::ImportContact => { self.contact_import_input.push(c); }
             MenuState::ExportContact => { self.contact_export_name.push(c); }
+            MenuState::Contacts => { self.contact_edit_input.push(c); }
+            MenuState::ContactDetails | MenuState::Requests => {}
             MenuState::Settings => {
-                match self.settings_selected_field {
-                    0 => { self.settings_server_url.push(c); }
-                    1 => { self.settings_polling_interval.push(c); }
-                    _ => {}
+                if let Some(field) = self.selected_settings_field() {
+                    field.push(c);
                 }
             }
-            _ => {}
         }
     }
 
@@ -633,6 +979,7 @@ impl App {
             ("/import", "Import a contact from JSON"),
             ("/export", "Export your contact info as JSON"),
             ("/contacts", "View all contacts"),
+            ("/requests", "Review contacts who answered your invites"),
             ("/settings", "View settings"),
             ("/quit", "Quit application"),
         ];
@@ -657,9 +1004,7 @@ pub fn char_to_byte_index(s: &str, char_idx: usize) -> usize {
 }
 
 fn make_auto_launch() -> Option<auto_launch::AutoLaunch> {
-    let exe = std::env::current_exe().ok()?;
-    let dir = exe.parent()?;
-    let daemon = dir.join("trassenger-daemon");
+    let daemon = crate::ipc::daemon_path()?;
     let daemon_str = daemon.to_string_lossy().to_string();
 
     auto_launch::AutoLaunchBuilder::new()
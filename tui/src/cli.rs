@@ -0,0 +1,431 @@
+// Non-interactive CLI — one-shot commands against the daemon for scripts,
+// cron jobs and shell pipelines. Shares the IPC client with the TUI.
+
+use std::io::Read;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::event::AppEvent;
+use crate::ipc::{DaemonClient, DaemonEvent};
+use crate::storage::{Message, Peer};
+
+/// How long to wait for the daemon to answer a command
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// A send waits for the post, which may try several mailboxes
+const SEND_TIMEOUT: Duration = Duration::from_secs(120);
+
+const USAGE: &str = "\
+Usage: trassenger-tui [--no-spawn] <command> [args]
+
+Commands:
+  send --to <name> [text]    Send a message (reads stdin if text is omitted or '-')
+  peers [--json]             List contacts
+  history <name> [--json]    Print the conversation with a contact
+  import <file|invite>       Import a contact card or invite code ('-' reads stdin)
+  export <name> [--invite]   Print your contact card (or invite code) under the given name
+  status [--json]            Show daemon status
+  watch [--json] [--from <name>] [--type <type>]
+                             Print incoming messages as they arrive (one per line)
+
+Run without a command to start the interactive TUI.";
+
+const COMMANDS: &[&str] = &["send", "peers", "history", "import", "export", "status", "watch", "help", "--help", "-h"];
+
+/// Returns the command-line arguments if they select a CLI command,
+/// or None if the interactive TUI should start.
+pub fn command_args(args: &[String]) -> Option<Vec<String>> {
+    let rest: Vec<String> = args.iter()
+        .skip(1)
+        .filter(|a| *a != "--no-spawn")
+        .cloned()
+        .collect();
+    let first = rest.first()?;
+    COMMANDS.contains(&first.as_str()).then_some(rest)
+}
+
+/// Run a CLI command and return the process exit code.
+pub async fn run(args: &[String], no_spawn: bool) -> i32 {
+    match run_command(args, no_spawn).await {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+async fn run_command(args: &[String], no_spawn: bool) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    if matches!(command.as_str(), "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let (mut client, events) = connect(no_spawn).await?;
+    let json = rest.iter().any(|a| a == "--json");
+
+    match command.as_str() {
+        "send" => cmd_send(&mut client, rest).await,
+        "peers" => cmd_peers(&mut client, json).await,
+        "history" => cmd_history(&mut client, rest, json).await,
+        "import" => cmd_import(&mut client, rest).await,
+        "export" => cmd_export(&mut client, rest).await,
+        "status" => cmd_status(&mut client, json).await,
+        "watch" => cmd_watch(&mut client, events, rest, json).await,
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Connect to the daemon. The returned receiver carries pushed events (NewMessage etc.),
+/// which only `watch` consumes.
+async fn connect(no_spawn: bool) -> Result<(DaemonClient, mpsc::UnboundedReceiver<AppEvent>), String> {
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    let client = if no_spawn {
+        DaemonClient::connect(event_tx).await?
+    } else {
+        DaemonClient::connect_or_spawn(event_tx).await?
+    };
+    Ok((client, event_rx))
+}
+
+// ── Commands ──────────────────────────────────────────────────────────────────
+
+async fn cmd_send(client: &mut DaemonClient, args: &[String]) -> Result<(), String> {
+    let mut to = None;
+    let mut words = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--to" => to = iter.next().cloned(),
+            _ => words.push(arg.clone()),
+        }
+    }
+    let to = to.ok_or("send requires --to <name>")?;
+
+    let text = if words.is_empty() || words == ["-"] {
+        read_stdin()?
+    } else {
+        words.join(" ")
+    };
+    let text = text.trim_end_matches('\n').to_string();
+    if text.trim().is_empty() {
+        return Err("Message is empty".to_string());
+    }
+
+    let peer = find_peer(&load_peers(client).await?, &to)?;
+    client.send_message_and_wait(&peer.queue_id, &text, &peer.encrypt_pk);
+    wait_for_within(client, SEND_TIMEOUT, |ev| matches!(ev, DaemonEvent::MessageSent).then_some(())).await
+}
+
+async fn cmd_peers(client: &mut DaemonClient, json: bool) -> Result<(), String> {
+    let peers = load_peers(client).await?;
+    if json {
+        print_json(&peers)?;
+    } else {
+        for peer in &peers {
+            println!("{}", peer.name);
+        }
+    }
+    Ok(())
+}
+
+async fn cmd_history(client: &mut DaemonClient, args: &[String], json: bool) -> Result<(), String> {
+    let name = positional(args).ok_or("history requires a contact name")?;
+    let peer = find_peer(&load_peers(client).await?, &name)?;
+
+    client.load_messages(&peer.queue_id);
+    let messages = wait_for(client, |ev| match ev {
+        DaemonEvent::Messages { queue_id, messages } if queue_id == peer.queue_id => Some(messages),
+        _ => None,
+    }).await?;
+
+    if json {
+        print_json(&messages)?;
+    } else {
+        for msg in &messages {
+            println!("{}", format_message(msg, &peer));
+        }
+    }
+    Ok(())
+}
+
+async fn cmd_import(client: &mut DaemonClient, args: &[String]) -> Result<(), String> {
+    let source = positional(args).ok_or("import requires a file path or invite code ('-' for stdin)")?;
+    let json = if source == "-" {
+        read_stdin()?
+    } else if crate::contact_card::is_invite(&source) {
+        source
+    } else {
+        let path = shellexpand::tilde(&source).to_string();
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+    };
+
+    client.import_contact(&json);
+    let (peer, warning) = wait_for(client, |ev| match ev {
+        DaemonEvent::ContactImported { peer, warning } => Some((peer, warning)),
+        DaemonEvent::ContactKeyChanged { peer } => {
+            let warning = format!("The key for '{}' has changed — verify their safety number again", peer.name);
+            Some((peer, Some(warning)))
+        }
+        _ => None,
+    }).await?;
+
+    if let Some(warning) = warning {
+        eprintln!("Warning: {}", warning);
+    }
+    println!("Imported contact '{}'", peer.name);
+    Ok(())
+}
+
+async fn cmd_export(client: &mut DaemonClient, args: &[String]) -> Result<(), String> {
+    let name = positional(args).ok_or("export requires your display name")?;
+
+    client.print_contact(&name);
+    let (json, invite) = wait_for(client, |ev| match ev {
+        DaemonEvent::ContactExported { json, invite, .. } => Some((json, invite)),
+        _ => None,
+    }).await?;
+
+    if args.iter().any(|a| a == "--invite") {
+        println!("{}", invite);
+    } else {
+        println!("{}", json);
+    }
+    Ok(())
+}
+
+async fn cmd_status(client: &mut DaemonClient, json: bool) -> Result<(), String> {
+    client.get_status();
+    let (server_url, interval, peer_count, keypair_loaded) = wait_for(client, |ev| match ev {
+        DaemonEvent::Status { server_url, polling_interval_secs, peer_count, keypair_loaded } => {
+            Some((server_url, polling_interval_secs, peer_count, keypair_loaded))
+        }
+        _ => None,
+    }).await?;
+
+    if json {
+        print_json(&serde_json::json!({
+            "running": true,
+            "server_url": server_url,
+            "polling_interval_secs": interval,
+            "peer_count": peer_count,
+            "keypair_loaded": keypair_loaded,
+        }))?;
+    } else {
+        println!("Daemon:            running");
+        println!("Server URL:        {}", server_url);
+        println!("Polling interval:  {}s", interval);
+        println!("Contacts:          {}", peer_count);
+        println!("Keypair:           {}", if keypair_loaded { "loaded" } else { "missing" });
+    }
+    Ok(())
+}
+
+async fn cmd_watch(
+    client: &mut DaemonClient,
+    mut events: mpsc::UnboundedReceiver<AppEvent>,
+    args: &[String],
+    json: bool,
+) -> Result<(), String> {
+    let mut from = Vec::new();
+    let mut types = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--from" => from.push(iter.next().cloned().ok_or("--from requires a contact name")?),
+            "--type" => types.push(iter.next().cloned().ok_or("--type requires a message type")?),
+            _ => {}
+        }
+    }
+
+    let mut peers = load_peers(client).await?;
+    let queue_filter = from.iter()
+        .map(|name| find_peer(&peers, name).map(|p| p.queue_id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    loop {
+        // Pushed events we don't print would otherwise pile up unread
+        let event = tokio::select! {
+            event = events.recv() => event,
+            response = client.recv() => match response {
+                Some(_) => continue,
+                None => None,
+            },
+        };
+        let msg = match event {
+            Some(AppEvent::NewMessage(msg)) => msg,
+            Some(_) => continue,
+            None => break,
+        };
+
+        if !queue_filter.is_empty() && !queue_filter.contains(&msg.queue_id) {
+            continue;
+        }
+        if !types.is_empty() && !types.contains(&msg.msg_type) {
+            continue;
+        }
+
+        // Contact imported while we were watching — refresh names. An error
+        // pushed earlier isn't the answer to this request, so drop it first.
+        if !peers.iter().any(|p| p.queue_id == msg.queue_id) {
+            client.try_recv_all();
+            peers = load_peers(client).await?;
+        }
+        let peer = peers.iter().find(|p| p.queue_id == msg.queue_id);
+
+        if json {
+            let line = serde_json::json!({
+                "id": msg.id,
+                "queue_id": msg.queue_id,
+                "from": peer.map(|p| p.name.as_str()),
+                "sender_id": msg.sender,
+                "content": msg.content,
+                "timestamp": msg.timestamp,
+                "type": msg.msg_type,
+            });
+            println!("{}", line);
+        } else {
+            match peer {
+                Some(peer) => println!("{}", format_message(&msg, peer)),
+                None => println!("{}", format_message(&msg, &unknown_peer(&msg))),
+            }
+        }
+    }
+
+    Err("Daemon disconnected".to_string())
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Wait for the daemon's answer to the last command, skipping unrelated pushes.
+async fn wait_for<T>(
+    client: &mut DaemonClient,
+    pick: impl FnMut(DaemonEvent) -> Option<T>,
+) -> Result<T, String> {
+    wait_for_within(client, RESPONSE_TIMEOUT, pick).await
+}
+
+async fn wait_for_within<T>(
+    client: &mut DaemonClient,
+    timeout: Duration,
+    mut pick: impl FnMut(DaemonEvent) -> Option<T>,
+) -> Result<T, String> {
+    let wait = async {
+        while let Some(ev) = client.recv().await {
+            if let DaemonEvent::Error { message } = ev {
+                return Err(message);
+            }
+            if let Some(value) = pick(ev) {
+                return Ok(value);
+            }
+        }
+        Err("Daemon disconnected".to_string())
+    };
+
+    tokio::time::timeout(timeout, wait)
+        .await
+        .map_err(|_| "Timed out waiting for daemon".to_string())?
+}
+
+async fn load_peers(client: &mut DaemonClient) -> Result<Vec<Peer>, String> {
+    client.load_peers();
+    wait_for(client, |ev| match ev {
+        DaemonEvent::Peers { peers } => Some(peers),
+        _ => None,
+    }).await
+}
+
+/// Find a contact by exact name, falling back to a unique case-insensitive match.
+fn find_peer(peers: &[Peer], name: &str) -> Result<Peer, String> {
+    if let Some(peer) = peers.iter().find(|p| p.name == name) {
+        return Ok(peer.clone());
+    }
+    let matches: Vec<&Peer> = peers.iter()
+        .filter(|p| p.name.eq_ignore_ascii_case(name))
+        .collect();
+    match matches.as_slice() {
+        [peer] => Ok((*peer).clone()),
+        [] => Err(format!("No contact named '{}'", name)),
+        _ => Err(format!("Contact name '{}' is ambiguous", name)),
+    }
+}
+
+/// First argument that isn't a flag
+fn positional(args: &[String]) -> Option<String> {
+    args.iter().find(|a| !a.starts_with("--")).cloned()
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut buf = String::new();
+    std::io::stdin()
+        .read_to_string(&mut buf)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(buf)
+}
+
+fn print_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| format!("Serialize: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Stand-in for a sender we have no contact for (shows the raw sender id)
+fn unknown_peer(msg: &Message) -> Peer {
+    Peer {
+        name: msg.sender.clone(),
+        encrypt_pk: msg.sender.clone(),
+        sign_pk: String::new(),
+        queue_id: msg.queue_id.clone(),
+        mailboxes: Vec::new(),
+        blocked: false,
+        verified: false,
+        key_changed_at: None,
+        notes: String::new(),
+        inbox: false,
+    }
+}
+
+fn format_message(msg: &Message, peer: &Peer) -> String {
+    let time = chrono::DateTime::from_timestamp(msg.timestamp, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "????-??-?? ??:??:??".to_string());
+    let sender = if msg.is_outbound { "You" } else { peer.name.as_str() };
+    format!("[{}] {}: {}", time, sender, msg.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    #[tokio::test]
+    async fn test_watch_ignores_stale_pushed_errors() {
+        let (ours, theirs) = tokio::io::duplex(64 * 1024);
+        let (event_tx, events) = mpsc::unbounded_channel();
+        let mut client = DaemonClient::from_stream(ours, event_tx).unwrap();
+
+        // A daemon that pushes an unrelated error, then a message from a
+        // contact the watcher doesn't know yet, which makes it reload contacts
+        let daemon = tokio::spawn(async move {
+            let (reader, mut writer) = tokio::io::split(theirs);
+            let mut commands = BufReader::new(reader).lines();
+            let peers = "{\"type\":\"Peers\",\"peers\":[]}\n";
+
+            commands.next_line().await.unwrap();
+            writer.write_all(peers.as_bytes()).await.unwrap();
+            writer.write_all(b"{\"type\":\"Error\",\"message\":\"Mailbox unreachable\"}\n").await.unwrap();
+            writer.write_all(concat!(
+                "{\"type\":\"NewMessage\",\"message\":{\"id\":\"m1\",\"queue_id\":\"q1\",\"sender\":\"s1\",",
+                "\"content\":\"hi\",\"timestamp\":0,\"msg_type\":\"text\",\"status\":\"delivered\",\"is_outbound\":false}}\n",
+            ).as_bytes()).await.unwrap();
+
+            let reload = commands.next_line().await.unwrap();
+            writer.write_all(peers.as_bytes()).await.unwrap();
+            reload
+        });
+
+        let result = cmd_watch(&mut client, events, &[], true).await;
+        assert_eq!(result, Err("Daemon disconnected".to_string()));
+        assert!(daemon.await.unwrap().is_some_and(|cmd| cmd.contains("LoadPeers")));
+    }
+}
@@ -1,5 +1,5 @@
-/// Centralized configuration constants
-/// Single source of truth for app defaults
+//! Centralized configuration constants
+//! Single source of truth for app defaults
 
 /// Default mailbox server URL
 /// Deno Deploy mailbox server
@@ -7,3 +7,13 @@ pub const DEFAULT_SERVER_URL: &str = "https://trassenger-mailbox.deno.dev";
 
 /// Default polling interval in seconds
 pub const DEFAULT_POLLING_INTERVAL: u64 = 10;
+
+/// Default time limit for user hook commands, in seconds
+pub const DEFAULT_HOOK_TIMEOUT: u64 = 10;
+
+/// How long the rendezvous queue in an exported invite is watched, in seconds
+pub const RENDEZVOUS_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Proxy used for the `"proxy": "tor"` preset: a local Tor client's SOCKS port,
+/// with hostnames resolved by Tor (socks5h) so DNS doesn't leak either
+pub const TOR_PROXY_URL: &str = "socks5h://127.0.0.1:9050";
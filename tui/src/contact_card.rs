@@ -0,0 +1,315 @@
+//! Contact cards: what one user hands another to be added as a contact.
+//!
+//! A card names its owner, carries their public keys and optionally the
+//! mailbox servers they read from. Version 1 cards are signed with the
+//! owner's Ed25519 key over every other field, so a card altered in transit
+//! (say, keys swapped under the same name) fails to import. Older unsigned
+//! cards are still accepted, with a warning.
+//!
+//! A card may name a one-time rendezvous queue. Whoever imports it posts
+//! their own signed card there, so the owner can add them back with one
+//! approval instead of a second manual import. A card can also advertise
+//! that its owner reads a public inbox, where strangers holding the card
+//! may send a first message.
+//!
+//! A signed card can also travel as an invite code: `trassenger1` followed by
+//! the card's fields in a compact binary form, with a 4-byte checksum, in
+//! base32. It fits in a chat message or a terminal QR code.
+
+use data_encoding::BASE32_NOPAD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::{self, Keypair};
+
+/// Card format written by `ContactCard::new_signed`
+pub const CARD_VERSION: u32 = 1;
+
+/// Domain separator for card signatures
+const SIGNATURE_CONTEXT: &str = "trassenger-card-v1";
+
+/// Start of every invite code
+pub const INVITE_PREFIX: &str = "trassenger1";
+
+/// Bytes of SHA-256 appended to an invite to catch typos
+const INVITE_CHECKSUM_LEN: usize = 4;
+
+/// Invite flag bit: the owner reads their public inbox
+const INVITE_FLAG_INBOX: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactCard {
+    /// 0 for legacy cards, which predate the field
+    #[serde(default)]
+    pub version: u32,
+    pub name: String,
+    pub encrypt_pk: String,
+    pub sign_pk: String,
+    /// Unix seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mailboxes: Vec<String>,
+    /// One-time queue for the importer's card in return (16 bytes, hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rendezvous: Option<String>,
+    /// The owner accepts first messages at their public inbox
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inbox: bool,
+    /// Hex Ed25519 signature by `sign_pk` over the other fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// What a card's signature tells us
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardTrust {
+    /// Signed by the key it carries
+    Signed,
+    /// Legacy card with no signature: nothing proves it wasn't altered
+    Unsigned,
+}
+
+impl ContactCard {
+    /// A current-format card for `keypair`, signed with its `sign_sk`
+    pub fn new_signed(
+        name: &str,
+        keypair: &Keypair,
+        mailboxes: Vec<String>,
+        rendezvous: Option<String>,
+        inbox: bool,
+    ) -> Result<Self, String> {
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("System clock error: {}", e))?
+            .as_secs() as i64;
+
+        let mut card = Self {
+            version: CARD_VERSION,
+            name: name.to_string(),
+            encrypt_pk: crypto::to_hex(&keypair.encrypt_pk),
+            sign_pk: crypto::to_hex(&keypair.sign_pk),
+            created_at: Some(created_at),
+            mailboxes,
+            rendezvous,
+            inbox,
+            signature: None,
+        };
+        let signed = crypto::sign_message(&card.signed_bytes(), &keypair.sign_sk)?;
+        card.signature = Some(crypto::to_hex(&signed[..64]));
+        Ok(card)
+    }
+
+    /// Check the self-signature. Legacy (version 0) cards are
+    /// `CardTrust::Unsigned`; a missing or mismatched signature on a newer
+    /// card is an error.
+    pub fn verify(&self) -> Result<CardTrust, String> {
+        let Some(signature) = &self.signature else {
+            if self.version == 0 {
+                return Ok(CardTrust::Unsigned);
+            }
+            return Err("Contact card signature is missing — the card may have been tampered with".to_string());
+        };
+        if self.version == 0 || self.version > CARD_VERSION {
+            return Err(format!("Unsupported contact card version {}", self.version));
+        }
+
+        let sign_pk = crypto::from_hex(&self.sign_pk)?;
+        let mut signed = crypto::from_hex(signature)?;
+        if signed.len() != 64 {
+            return Err("Invalid card signature length".to_string());
+        }
+        signed.extend(self.signed_bytes());
+        crypto::verify_signature(&signed, &sign_pk)
+            .map_err(|_| "Contact card signature is invalid — the card may have been tampered with".to_string())?;
+        Ok(CardTrust::Signed)
+    }
+
+    /// Encode a signed card as an invite code
+    pub fn to_invite(&self) -> Result<String, String> {
+        let signature = self.signature.as_deref().ok_or("Only signed contact cards can be shared as invites")?;
+        let created_at = self.created_at.ok_or("Contact card has no creation time")?;
+
+        let mut payload = vec![self.version as u8];
+        payload.extend(fixed_hex::<32>(&self.encrypt_pk)?);
+        payload.extend(fixed_hex::<32>(&self.sign_pk)?);
+        payload.extend(created_at.to_be_bytes());
+        payload.extend(fixed_hex::<64>(signature)?);
+        push_str(&mut payload, &self.name)?;
+        payload.push(u8::try_from(self.mailboxes.len()).map_err(|_| "Too many mailbox servers for an invite")?);
+        for url in &self.mailboxes {
+            push_str(&mut payload, url)?;
+        }
+        if let Some(rendezvous) = &self.rendezvous {
+            payload.extend(fixed_hex::<16>(rendezvous)?);
+        }
+        if self.inbox {
+            payload.push(INVITE_FLAG_INBOX);
+        }
+
+        let checksum = Sha256::digest(&payload);
+        payload.extend(&checksum[..INVITE_CHECKSUM_LEN]);
+        Ok(format!("{}{}", INVITE_PREFIX, BASE32_NOPAD.encode(&payload).to_lowercase()))
+    }
+
+    /// Decode an invite code. Case, whitespace and dashes are ignored, so
+    /// wrapped or retyped codes still work. The signature is not checked
+    /// here; call `verify` as for any card.
+    pub fn from_invite(invite: &str) -> Result<Self, String> {
+        let compact: String = invite
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect::<String>()
+            .to_uppercase();
+        let encoded = compact
+            .strip_prefix(&INVITE_PREFIX.to_uppercase())
+            .ok_or("Not a Trassenger invite code")?;
+        let data = BASE32_NOPAD
+            .decode(encoded.as_bytes())
+            .map_err(|_| "Invite code contains invalid characters")?;
+
+        let split = data.len().checked_sub(INVITE_CHECKSUM_LEN).ok_or("Invite code is too short")?;
+        let (payload, checksum) = data.split_at(split);
+        if Sha256::digest(payload)[..INVITE_CHECKSUM_LEN] != *checksum {
+            return Err("Invite code checksum doesn't match — check it for typos".to_string());
+        }
+
+        let mut reader = Reader(payload);
+        let version = reader.byte()? as u32;
+        let encrypt_pk = crypto::to_hex(reader.take(32)?);
+        let sign_pk = crypto::to_hex(reader.take(32)?);
+        let created_at = i64::from_be_bytes(reader.take(8)?.try_into().map_err(|_| "Invite code is truncated")?);
+        let signature = crypto::to_hex(reader.take(64)?);
+        let name = reader.string()?;
+        let mailboxes = (0..reader.byte()?).map(|_| reader.string()).collect::<Result<Vec<_>, _>>()?;
+        // Optional tail: a 16-byte rendezvous id, then a flags byte
+        let rendezvous = match reader.0.len() {
+            16 | 17 => Some(crypto::to_hex(reader.take(16)?)),
+            _ => None,
+        };
+        let flags = match reader.0.len() {
+            0 => 0,
+            1 => reader.byte()?,
+            _ => return Err("Invite code has trailing data".to_string()),
+        };
+
+        Ok(Self {
+            version,
+            name,
+            encrypt_pk,
+            sign_pk,
+            created_at: Some(created_at),
+            mailboxes,
+            rendezvous,
+            inbox: flags & INVITE_FLAG_INBOX != 0,
+            signature: Some(signature),
+        })
+    }
+
+    /// Bytes covered by the signature: every field but the signature, as a
+    /// JSON array so the encoding is unambiguous. `rendezvous` and `inbox`
+    /// are appended only when set, so cards without them sign as before.
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut fields = vec![
+            serde_json::json!(SIGNATURE_CONTEXT),
+            serde_json::json!(self.version),
+            serde_json::json!(self.name),
+            serde_json::json!(self.encrypt_pk),
+            serde_json::json!(self.sign_pk),
+            serde_json::json!(self.created_at),
+            serde_json::json!(self.mailboxes),
+        ];
+        if let Some(rendezvous) = &self.rendezvous {
+            fields.push(serde_json::json!(rendezvous));
+        }
+        if self.inbox {
+            fields.push(serde_json::json!({ "inbox": true }));
+        }
+        serde_json::Value::Array(fields).to_string().into_bytes()
+    }
+}
+
+/// Whether `input` looks like an invite code rather than card JSON
+pub fn is_invite(input: &str) -> bool {
+    input.trim_start().get(..INVITE_PREFIX.len()).is_some_and(|p| p.eq_ignore_ascii_case(INVITE_PREFIX))
+}
+
+fn fixed_hex<const N: usize>(hex: &str) -> Result<[u8; N], String> {
+    crypto::from_hex(hex)?
+        .try_into()
+        .map_err(|_| format!("Expected a {}-byte key or signature", N))
+}
+
+/// Length-prefixed UTF-8 string (up to 255 bytes)
+fn push_str(out: &mut Vec<u8>, value: &str) -> Result<(), String> {
+    let len = u8::try_from(value.len()).map_err(|_| format!("'{}' is too long for an invite", value))?;
+    out.push(len);
+    out.extend(value.as_bytes());
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("Invite code is truncated".to_string());
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.byte()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Invite code contains invalid text".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_card_detects_tampering() {
+        let alice = crypto::generate_keypair();
+        let rendezvous = Some("00112233445566778899aabbccddeeff".to_string());
+        let card = ContactCard::new_signed("Alice", &alice, vec!["https://relay.example".to_string()], rendezvous, true).unwrap();
+
+        let json = serde_json::to_string(&card).unwrap();
+        let parsed: ContactCard = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.verify(), Ok(CardTrust::Signed));
+
+        // Swapping the encryption key under the same name breaks the signature
+        let mallory = crypto::generate_keypair();
+        let tampered = ContactCard { encrypt_pk: crypto::to_hex(&mallory.encrypt_pk), ..card.clone() };
+        assert!(tampered.verify().is_err());
+        let tampered = ContactCard { mailboxes: Vec::new(), ..card.clone() };
+        assert!(tampered.verify().is_err());
+        let tampered = ContactCard { rendezvous: None, ..card.clone() };
+        assert!(tampered.verify().is_err());
+        let tampered = ContactCard { inbox: false, ..card.clone() };
+        assert!(tampered.verify().is_err());
+        // Stripping the signature doesn't downgrade a current card to legacy
+        let stripped = ContactCard { signature: None, ..card.clone() };
+        assert!(stripped.verify().is_err());
+
+        // Invite codes round-trip, tolerate reformatting and catch typos
+        let invite = card.to_invite().unwrap();
+        assert!(is_invite(&invite));
+        assert_eq!(ContactCard::from_invite(&invite).unwrap(), card);
+        let wrapped = invite.to_uppercase().as_bytes().chunks(40).map(|c| std::str::from_utf8(c).unwrap()).collect::<Vec<_>>().join("\n");
+        assert_eq!(ContactCard::from_invite(&wrapped).unwrap(), card);
+        let mut typo = invite.clone().into_bytes();
+        let last = typo.len() - 10;
+        typo[last] = if typo[last] == b'a' { b'b' } else { b'a' };
+        assert!(ContactCard::from_invite(&String::from_utf8(typo).unwrap()).unwrap_err().contains("checksum"));
+
+        let legacy: ContactCard = serde_json::from_str(r#"{"name":"Bob","encrypt_pk":"00","sign_pk":"00"}"#).unwrap();
+        assert_eq!(legacy.verify(), Ok(CardTrust::Unsigned));
+    }
+}
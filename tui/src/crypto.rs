@@ -117,6 +117,32 @@ pub fn decrypt_message(
         .map_err(|_| "Decryption failed".to_string())
 }
 
+/// Deletion-capability token for a posted envelope.
+/// Derived from the X25519 shared secret, so only the two conversation parties
+/// can compute it. The server stores `delete_commitment(token)` and releases
+/// the message for deletion only to whoever presents the preimage.
+pub fn derive_delete_token(my_sk: &[u8], their_pk: &[u8], envelope: &[u8]) -> Result<Vec<u8>, String> {
+    if my_sk.len() != 32 || their_pk.len() != 32 {
+        return Err("Invalid key length".to_string());
+    }
+
+    let my_sk_bytes: [u8; 32] = my_sk.try_into().unwrap();
+    let their_pk_bytes: [u8; 32] = their_pk.try_into().unwrap();
+    let shared_secret = X25519SecretKey::from(my_sk_bytes)
+        .diffie_hellman(&X25519PublicKey::from(their_pk_bytes));
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"trassenger-delete-v1");
+    hasher.update(shared_secret.as_bytes());
+    hasher.update(Sha256::digest(envelope));
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Hash commitment to a deletion token (sent with the post)
+pub fn delete_commitment(token: &[u8]) -> Vec<u8> {
+    Sha256::digest(token).to_vec()
+}
+
 /// Sign a message using Ed25519
 pub fn sign_message(message: &[u8], sign_sk: &[u8]) -> Result<Vec<u8>, String> {
     // Accept both 32-byte seed and 64-byte expanded key (sodiumoxide compat)
@@ -203,6 +229,47 @@ pub fn generate_conversation_queue_id(pk1_hex: &str, pk2_hex: &str) -> Result<St
     Ok(queue_id)
 }
 
+/// Public inbox queue for an encryption key: where anyone holding the
+/// owner's card can send a first message before being added as a contact
+pub fn inbox_queue_id(encrypt_pk: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"trassenger-inbox-v1");
+    hasher.update(encrypt_pk);
+    hex::encode(&hasher.finalize()[..16])
+}
+
+/// Safety number for a conversation: twelve 5-digit groups both parties
+/// compute identically from each side's signing and encryption keys.
+/// Comparing it out of band shows neither key was swapped in transit.
+pub fn safety_number(
+    my_sign_pk: &[u8],
+    my_encrypt_pk: &[u8],
+    their_sign_pk: &[u8],
+    their_encrypt_pk: &[u8],
+) -> Vec<String> {
+    let mut halves = [fingerprint_digits(my_sign_pk, my_encrypt_pk), fingerprint_digits(their_sign_pk, their_encrypt_pk)];
+    // Same order on both sides
+    halves.sort();
+    halves.concat()
+}
+
+/// Six 5-digit groups identifying one party's keys
+fn fingerprint_digits(sign_pk: &[u8], encrypt_pk: &[u8]) -> Vec<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"trassenger-safety-v1");
+    hasher.update(sign_pk);
+    hasher.update(encrypt_pk);
+    let hash = hasher.finalize();
+
+    hash[..30]
+        .chunks(5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,4 +344,40 @@ mod tests {
         assert_eq!(queue_id_1, queue_id_2);
         println!("Deterministic queue_id: {}", queue_id_1);
     }
+
+    #[test]
+    fn test_delete_token_matches_on_both_sides() {
+        let alice = generate_keypair();
+        let bob = generate_keypair();
+        let mallory = generate_keypair();
+        let envelope = b"base64-envelope";
+
+        let sender_token = derive_delete_token(&alice.encrypt_sk, &bob.encrypt_pk, envelope).unwrap();
+        let recipient_token = derive_delete_token(&bob.encrypt_sk, &alice.encrypt_pk, envelope).unwrap();
+        assert_eq!(sender_token, recipient_token);
+
+        let other_envelope = derive_delete_token(&bob.encrypt_sk, &alice.encrypt_pk, b"other").unwrap();
+        let outsider = derive_delete_token(&mallory.encrypt_sk, &alice.encrypt_pk, envelope).unwrap();
+        assert_ne!(recipient_token, other_envelope);
+        assert_ne!(recipient_token, outsider);
+
+        assert_eq!(delete_commitment(&recipient_token), delete_commitment(&sender_token));
+        assert_ne!(delete_commitment(&recipient_token), recipient_token);
+    }
+
+    #[test]
+    fn test_safety_number_is_symmetric() {
+        let alice = generate_keypair();
+        let bob = generate_keypair();
+
+        let for_alice = safety_number(&alice.sign_pk, &alice.encrypt_pk, &bob.sign_pk, &bob.encrypt_pk);
+        let for_bob = safety_number(&bob.sign_pk, &bob.encrypt_pk, &alice.sign_pk, &alice.encrypt_pk);
+        assert_eq!(for_alice, for_bob);
+        assert_eq!(for_alice.len(), 12);
+        assert!(for_alice.iter().all(|g| g.len() == 5 && g.bytes().all(|b| b.is_ascii_digit())));
+
+        // A different key for Bob gives a different number
+        let mallory = generate_keypair();
+        assert_ne!(for_alice, safety_number(&alice.sign_pk, &alice.encrypt_pk, &bob.sign_pk, &mallory.encrypt_pk));
+    }
 }
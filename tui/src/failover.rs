@@ -0,0 +1,277 @@
+//! Failover across several mailbox servers.
+//!
+//! `Config::server_url` followed by `Config::fallback_servers` form an ordered
+//! list. Posts go to the first healthy server; fetches read every server, so
+//! nothing is stranded on a fallback that was used during an outage. A server
+//! that can't be reached is marked down (30s, doubling up to 10 minutes): it's
+//! skipped while down, then probed again, and one success marks it healthy.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::future;
+use tokio::sync::mpsc;
+
+use crate::mailbox::{MailboxError, MessageMeta, ServerMessage};
+use crate::transport::{FetchResults, Transport};
+
+/// How long a server stays down after its first failure; doubles per failure
+const DOWN_BASE: Duration = Duration::from_secs(30);
+
+/// Longest a server stays marked down
+const DOWN_MAX: Duration = Duration::from_secs(600);
+
+struct Server {
+    url: String,
+    transport: Arc<dyn Transport>,
+    health: Mutex<Health>,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    failures: u32,
+    down_until: Option<Instant>,
+}
+
+impl Server {
+    fn is_down(&self) -> bool {
+        self.health
+            .lock()
+            .ok()
+            .and_then(|h| h.down_until)
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    fn record_success(&self) {
+        if let Ok(mut health) = self.health.lock() {
+            if health.failures > 0 {
+                crate::logger::log_to_file(&format!("Mailbox {} is reachable again", self.url));
+            }
+            *health = Health::default();
+        }
+    }
+
+    fn record_failure(&self, error: &MailboxError) {
+        if let Ok(mut health) = self.health.lock() {
+            health.failures += 1;
+            let wait = DOWN_BASE.saturating_mul(1 << (health.failures - 1).min(5)).min(DOWN_MAX);
+            health.down_until = Some(Instant::now() + wait);
+            if health.failures == 1 {
+                crate::logger::log_to_file(&format!("Mailbox {} is unavailable ({}), failing over", self.url, error));
+            }
+        }
+    }
+}
+
+/// Whether an error says the server is unusable, rather than rejecting this
+/// particular request
+fn is_outage(error: &MailboxError) -> bool {
+    matches!(
+        error,
+        MailboxError::Network(_)
+            | MailboxError::RateLimited { .. }
+            | MailboxError::Server { .. }
+            | MailboxError::PinMismatch(_)
+    )
+}
+
+pub struct FailoverMailbox {
+    servers: Vec<Server>,
+    /// Server each fetched message came from, so its delete goes back there
+    origins: Mutex<HashMap<String, usize>>,
+}
+
+impl FailoverMailbox {
+    /// `servers` in order of preference: (URL for logging, transport)
+    pub fn new(servers: Vec<(String, Arc<dyn Transport>)>) -> Self {
+        Self {
+            servers: servers
+                .into_iter()
+                .map(|(url, transport)| Server { url, transport, health: Mutex::new(Health::default()) })
+                .collect(),
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Server indices to try: healthy ones first, in preference order, then
+    /// the ones marked down as a last resort
+    fn preference_order(&self) -> Vec<usize> {
+        let (up, down): (Vec<usize>, Vec<usize>) = (0..self.servers.len()).partition(|&i| !self.servers[i].is_down());
+        up.into_iter().chain(down).collect()
+    }
+
+    /// Servers worth polling now: every healthy one, or all of them if none are
+    fn servers_to_poll(&self) -> Vec<usize> {
+        let up: Vec<usize> = (0..self.servers.len()).filter(|&i| !self.servers[i].is_down()).collect();
+        if up.is_empty() { (0..self.servers.len()).collect() } else { up }
+    }
+}
+
+#[async_trait]
+impl Transport for FailoverMailbox {
+    async fn post(&self, queue_id: &str, data: String, meta: MessageMeta) -> Result<String, MailboxError> {
+        let mut last_error = None;
+        for i in self.preference_order() {
+            let server = &self.servers[i];
+            match server.transport.post(queue_id, data.clone(), meta.clone()).await {
+                Ok(id) => {
+                    server.record_success();
+                    return Ok(id);
+                }
+                Err(e) if is_outage(&e) => {
+                    server.record_failure(&e);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| MailboxError::Protocol("No mailbox servers configured".to_string())))
+    }
+
+    async fn fetch(&self, queue_id: &str) -> Result<Vec<ServerMessage>, MailboxError> {
+        let mut results = self.fetch_many(&[queue_id.to_string()]).await;
+        results
+            .pop()
+            .map(|(_, result)| result)
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+
+    /// Fetch from every server and merge per queue. A queue only fails if no
+    /// server could be read.
+    async fn fetch_many(&self, queue_ids: &[String]) -> FetchResults {
+        let polled = self.servers_to_poll();
+        let per_server = future::join_all(polled.iter().map(|&i| self.servers[i].transport.fetch_many(queue_ids))).await;
+
+        // Per queue: messages from every server that answered, and the last error
+        let mut merged: HashMap<String, (Option<Vec<ServerMessage>>, Option<MailboxError>)> = HashMap::new();
+        for (&i, results) in polled.iter().zip(per_server) {
+            let server = &self.servers[i];
+            let outage = results.iter().all(|(_, r)| r.as_ref().is_err_and(is_outage));
+            match results.first() {
+                Some((_, Err(e))) if outage => server.record_failure(e),
+                _ => server.record_success(),
+            }
+
+            for (queue_id, result) in results {
+                let (found, error) = merged.entry(queue_id).or_default();
+                match result {
+                    Ok(messages) => {
+                        if let Ok(mut origins) = self.origins.lock() {
+                            origins.extend(messages.iter().map(|m| (m.id.clone(), i)));
+                        }
+                        found.get_or_insert_with(Vec::new).extend(messages);
+                    }
+                    Err(e) => *error = Some(e),
+                }
+            }
+        }
+
+        merged
+            .into_iter()
+            .map(|(queue_id, (found, error))| {
+                let result = match (found, error) {
+                    (Some(mut messages), _) => {
+                        messages.sort_by_key(|m| m.timestamp);
+                        Ok(messages)
+                    }
+                    (None, Some(e)) => Err(e),
+                    (None, None) => Ok(Vec::new()),
+                };
+                (queue_id, result)
+            })
+            .collect()
+    }
+
+    async fn delete(&self, queue_id: &str, message_id: &str, delete_token: Option<&str>) -> Result<(), MailboxError> {
+        let origin = self.origins.lock().ok().and_then(|mut o| o.remove(message_id));
+        match origin {
+            Some(i) => self.servers[i].transport.delete(queue_id, message_id, delete_token).await,
+            // Not fetched through us: ask each server until one accepts
+            None => {
+                let mut last_error = None;
+                for server in &self.servers {
+                    match server.transport.delete(queue_id, message_id, delete_token).await {
+                        Ok(()) => return Ok(()),
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                Err(last_error.unwrap_or_else(|| MailboxError::Protocol("No mailbox servers configured".to_string())))
+            }
+        }
+    }
+
+    /// Push is only used when every server can stream; otherwise the daemon
+    /// polls, which covers all of them. The merged stream closes as soon as
+    /// any server's stream does, so the caller resubscribes everywhere.
+    async fn subscribe(&self, queue_ids: Vec<String>) -> Result<Option<mpsc::UnboundedReceiver<String>>, MailboxError> {
+        let mut receivers = Vec::with_capacity(self.servers.len());
+        for server in &self.servers {
+            match server.transport.subscribe(queue_ids.clone()).await? {
+                Some(rx) => receivers.push(rx),
+                None => return Ok(None),
+            }
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (next, _, _) = future::select_all(receivers.iter_mut().map(|r| Box::pin(r.recv()))).await;
+                let Some(queue_id) = next else { return };
+                if tx.send(queue_id).is_err() {
+                    return; // Subscriber went away
+                }
+            }
+        });
+        Ok(Some(rx))
+    }
+
+    /// Only back off when every server has asked us to
+    fn backoff_remaining(&self) -> Option<Duration> {
+        self.servers
+            .iter()
+            .map(|s| s.transport.backoff_remaining())
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::FilesystemMailbox;
+    use crate::mailbox::MailboxClient;
+
+    fn meta() -> MessageMeta {
+        MessageMeta { filename: None, size: None, delete_hash: None }
+    }
+
+    #[tokio::test]
+    async fn test_posts_fail_over_and_fetches_merge() {
+        let root = std::env::temp_dir().join(format!("trassenger-failover-{}", uuid::Uuid::new_v4()));
+        let (dir_a, dir_b) = (root.join("a"), root.join("b"));
+        let mailbox = FailoverMailbox::new(vec![
+            ("dead".to_string(), Arc::new(MailboxClient::new("http://127.0.0.1:9".to_string())) as Arc<dyn Transport>),
+            ("a".to_string(), Arc::new(FilesystemMailbox::new(&dir_a))),
+            ("b".to_string(), Arc::new(FilesystemMailbox::new(&dir_b))),
+        ]);
+
+        // The primary is unreachable, so the post lands on the first fallback
+        let first = mailbox.post("queue1", "aGVsbG8=".to_string(), meta()).await.unwrap();
+        assert!(mailbox.servers[0].is_down());
+        assert_eq!(FilesystemMailbox::new(&dir_a).fetch("queue1").await.unwrap().len(), 1);
+
+        // Messages left on any server are picked up, and deletes go back to their origin
+        let second = FilesystemMailbox::new(&dir_b).post("queue1", "d29ybGQ=".to_string(), meta()).await.unwrap();
+        let ids: Vec<String> = mailbox.fetch("queue1").await.unwrap().into_iter().map(|m| m.id).collect();
+        assert!(ids.contains(&first) && ids.contains(&second));
+
+        mailbox.delete("queue1", &second, None).await.unwrap();
+        assert!(FilesystemMailbox::new(&dir_b).fetch("queue1").await.unwrap().is_empty());
+        assert_eq!(mailbox.fetch("queue1").await.unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,186 @@
+//! Filesystem mailbox transport — for air-gapped and offline use.
+//!
+//! Envelopes are plain files under a shared directory (network mount, synced
+//! folder, USB stick): `<root>/<queue_id>/<message_id>`. Fetch/delete behave
+//! like the HTTP mailbox, so two daemons pointed at the same folder can talk
+//! with no server at all.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::mailbox::{MailboxError, MessageMeta, ServerMessage};
+use crate::transport::Transport;
+
+/// On-disk envelope (one JSON file per message)
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    id: String,
+    timestamp: i64,
+    data: String,
+    meta: MessageMeta,
+}
+
+pub struct FilesystemMailbox {
+    root: PathBuf,
+}
+
+impl FilesystemMailbox {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn queue_dir(&self, queue_id: &str) -> Result<PathBuf, MailboxError> {
+        Ok(self.root.join(path_component(queue_id)?))
+    }
+}
+
+/// Reject ids that could escape the mailbox directory
+fn path_component(id: &str) -> Result<&str, MailboxError> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && !id.contains(['/', '\\', ':']);
+    if valid {
+        Ok(id)
+    } else {
+        Err(MailboxError::Protocol(format!("Invalid mailbox id: {:?}", id)))
+    }
+}
+
+async fn read_envelope(path: &Path) -> Option<Envelope> {
+    let json = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+#[async_trait]
+impl Transport for FilesystemMailbox {
+    async fn post(&self, queue_id: &str, data: String, meta: MessageMeta) -> Result<String, MailboxError> {
+        let dir = self.queue_dir(queue_id)?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| MailboxError::Network(format!("Failed to create queue directory: {}", e)))?;
+
+        let envelope = Envelope {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            data,
+            meta,
+        };
+        let json = serde_json::to_vec(&envelope)
+            .map_err(|e| MailboxError::Protocol(format!("Failed to serialize envelope: {}", e)))?;
+
+        // Write under a hidden temp name, then rename, so readers never see partial files
+        let tmp_path = dir.join(format!(".{}.tmp", envelope.id));
+        tokio::fs::write(&tmp_path, json)
+            .await
+            .map_err(|e| MailboxError::Network(format!("Failed to write message: {}", e)))?;
+        tokio::fs::rename(&tmp_path, dir.join(&envelope.id))
+            .await
+            .map_err(|e| MailboxError::Network(format!("Failed to write message: {}", e)))?;
+
+        Ok(envelope.id)
+    }
+
+    async fn fetch(&self, queue_id: &str) -> Result<Vec<ServerMessage>, MailboxError> {
+        let dir = self.queue_dir(queue_id)?;
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(e) => e,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(MailboxError::Network(format!("Failed to read queue directory: {}", e))),
+        };
+
+        let mut messages = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| MailboxError::Network(format!("Failed to read queue directory: {}", e)))?
+        {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue; // In-progress write
+            }
+            // Skip unreadable files (e.g. still syncing) — they'll be picked up next poll
+            if let Some(envelope) = read_envelope(&entry.path()).await {
+                // The file name, not the id inside, is what `delete` removes —
+                // sync tools rename conflicting copies
+                messages.push(ServerMessage {
+                    id: name,
+                    timestamp: envelope.timestamp,
+                    data: envelope.data,
+                });
+            }
+        }
+
+        messages.sort_by_key(|m| m.timestamp);
+        Ok(messages)
+    }
+
+    // No token check: anyone who can reach the directory can remove files anyway
+    async fn delete(&self, queue_id: &str, message_id: &str, _delete_token: Option<&str>) -> Result<(), MailboxError> {
+        let path = self.queue_dir(queue_id)?.join(path_component(message_id)?);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            // Already gone (deleted by another reader) — same outcome
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(MailboxError::Network(format!("Failed to delete message: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> MessageMeta {
+        MessageMeta { filename: None, size: None, delete_hash: None }
+    }
+
+    #[tokio::test]
+    async fn test_post_fetch_delete_roundtrip() {
+        let root = std::env::temp_dir().join(format!("trassenger-fs-{}", uuid::Uuid::new_v4()));
+        let mailbox = FilesystemMailbox::new(&root);
+
+        let first = mailbox.post("queue1", "aGVsbG8=".to_string(), meta()).await.unwrap();
+        let second = mailbox.post("queue1", "d29ybGQ=".to_string(), meta()).await.unwrap();
+
+        let messages = mailbox.fetch("queue1").await.unwrap();
+        let ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(ids.contains(&first.as_str()) && ids.contains(&second.as_str()));
+
+        mailbox.delete("queue1", &first, None).await.unwrap();
+        let messages = mailbox.fetch("queue1").await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, second);
+        assert_eq!(messages[0].data, "d29ybGQ=");
+
+        // Unknown queues are simply empty
+        assert!(mailbox.fetch("queue2").await.unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_renamed_file_can_be_deleted() {
+        let root = std::env::temp_dir().join(format!("trassenger-fs-{}", uuid::Uuid::new_v4()));
+        let mailbox = FilesystemMailbox::new(&root);
+
+        let id = mailbox.post("queue1", "aGVsbG8=".to_string(), meta()).await.unwrap();
+        let dir = root.join("queue1");
+        std::fs::rename(dir.join(&id), dir.join(format!("{} (conflicted copy)", id))).unwrap();
+
+        let messages = mailbox.fetch("queue1").await.unwrap();
+        assert_eq!(messages.len(), 1);
+        mailbox.delete("queue1", &messages[0].id, None).await.unwrap();
+        assert!(mailbox.fetch("queue1").await.unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_path_traversal() {
+        let mailbox = FilesystemMailbox::new(std::env::temp_dir());
+        assert!(mailbox.post("../escape", String::new(), meta()).await.is_err());
+        assert!(mailbox.delete("queue1", "../../etc/passwd", None).await.is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // IPC client — connects TUI to the daemon socket, sends commands, receives events.
 
 use tokio::sync::mpsc;
+use crate::polling_policy::PollingPolicy;
 use crate::storage;
 use crate::event::AppEvent;
 
@@ -18,15 +19,65 @@ fn pipe_name() -> String {
     r"\\.\pipe\trassenger".to_string()
 }
 
+// ── Daemon auto-start ─────────────────────────────────────────────────────────
+
+/// How long to wait for a freshly spawned daemon to open its socket
+const DAEMON_START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Path to the daemon binary (installed next to the TUI executable)
+pub fn daemon_path() -> Option<std::path::PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    Some(dir.join(format!("trassenger-daemon{}", std::env::consts::EXE_SUFFIX)))
+}
+
+/// Start the daemon in the background, detached from this terminal.
+fn spawn_daemon() -> Result<(), String> {
+    use std::process::{Command, Stdio};
+
+    let path = daemon_path().ok_or("Could not locate trassenger-daemon")?;
+    let mut cmd = Command::new(&path);
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group so the daemon outlives the terminal
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    cmd.spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start daemon {:?}: {}", path, e))
+}
+
 // ── Commands to daemon ────────────────────────────────────────────────────────
 
+/// Which kind of client a connection is; see `DaemonClient::hello`.
+/// Connections that never say hello count as one-shot CLI clients.
+#[derive(Debug, serde::Serialize, Clone, Copy)]
+pub enum ClientRole {
+    Tui,
+}
+
 #[derive(Debug, serde::Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum DaemonCommand {
+    Hello {
+        role: ClientRole,
+    },
     SendMessage {
         queue_id: String,
         plaintext: String,
         peer_encrypt_pk: String,
+        wait: bool,
     },
     LoadMessages {
         queue_id: String,
@@ -37,12 +88,48 @@ pub enum DaemonCommand {
     },
     ExportContact {
         name: String,
+        print_only: bool,
+    },
+    RenameContact {
+        queue_id: String,
+        name: String,
+    },
+    DeleteContact {
+        queue_id: String,
+        purge_history: bool,
+    },
+    BlockContact {
+        queue_id: String,
+        blocked: bool,
+    },
+    SetContactNotes {
+        queue_id: String,
+        notes: String,
+    },
+    GetSafetyNumber {
+        queue_id: String,
+    },
+    LoadContactRequests,
+    AcceptContactRequest {
+        id: String,
+    },
+    /// Drop the request; with `block`, add the sender as a blocked contact
+    RejectContactRequest {
+        id: String,
+        block: bool,
+    },
+    VerifyContact {
+        queue_id: String,
+        verified: bool,
     },
     UpdateConfig {
         server_url: String,
         polling_interval_secs: u64,
+        polling: PollingPolicy,
+        public_inbox: bool,
     },
     ResetPollingInterval,
+    GetStatus,
 }
 
 // ── Events from daemon ────────────────────────────────────────────────────────
@@ -62,17 +149,52 @@ pub enum DaemonEvent {
     },
     ContactImported {
         peer: storage::Peer,
+        #[serde(default)]
+        warning: Option<String>,
+    },
+    /// A re-import replaced a known contact's keys
+    ContactKeyChanged {
+        peer: storage::Peer,
     },
     ContactExported {
         json: String,
+        #[serde(default)]
+        invite: String,
+        #[serde(default)]
+        saved_to: Option<String>,
+    },
+    SafetyNumber {
+        queue_id: String,
+        groups: Vec<String>,
+    },
+    ContactRequests {
+        requests: Vec<storage::ContactRequest>,
+    },
+    /// Someone answered one of our invites
+    ContactRequest {
+        request: storage::ContactRequest,
     },
     MessageSent,
     PollingInterval {
         secs: u64,
     },
+    Status {
+        server_url: String,
+        polling_interval_secs: u64,
+        peer_count: usize,
+        keypair_loaded: bool,
+    },
     Error {
         message: String,
     },
+    /// The mailbox's certificate stopped (Some) or resumed (None) matching its pin
+    CertificateWarning {
+        message: Option<String>,
+    },
+    /// New settings are in effect
+    ConfigChanged {
+        config: storage::Config,
+    },
 }
 
 // ── DaemonClient ─────────────────────────────────────────────────────────────
@@ -98,6 +220,27 @@ impl DaemonClient {
         }
     }
 
+    /// Connect to the daemon, starting it first if it is not running.
+    /// Waits up to `DAEMON_START_TIMEOUT` for the socket to come up.
+    pub async fn connect_or_spawn(event_tx: mpsc::UnboundedSender<AppEvent>) -> Result<Self, String> {
+        if let Ok(client) = Self::connect(event_tx.clone()).await {
+            return Ok(client);
+        }
+
+        crate::logger::log_to_file("[ipc] Daemon not running, starting it");
+        spawn_daemon()?;
+
+        let deadline = std::time::Instant::now() + DAEMON_START_TIMEOUT;
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            match Self::connect(event_tx.clone()).await {
+                Ok(client) => return Ok(client),
+                Err(e) if std::time::Instant::now() >= deadline => return Err(e),
+                Err(_) => {}
+            }
+        }
+    }
+
     #[cfg(unix)]
     async fn connect_unix(event_tx: mpsc::UnboundedSender<AppEvent>) -> Result<Self, String> {
         use tokio::net::UnixStream;
@@ -121,7 +264,7 @@ impl DaemonClient {
         Self::from_stream(stream, event_tx)
     }
 
-    fn from_stream<S>(stream: S, event_tx: mpsc::UnboundedSender<AppEvent>) -> Result<Self, String>
+    pub(crate) fn from_stream<S>(stream: S, event_tx: mpsc::UnboundedSender<AppEvent>) -> Result<Self, String>
     where
         S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + 'static,
     {
@@ -208,6 +351,12 @@ impl DaemonClient {
 
     // Convenience methods
 
+    /// Tell the daemon this connection is an interactive TUI, so it polls
+    /// fast and holds back desktop notifications while we're open.
+    pub fn hello(&self) {
+        self.send_command(DaemonCommand::Hello { role: ClientRole::Tui });
+    }
+
     pub fn load_peers(&self) {
         self.send_command(DaemonCommand::LoadPeers);
     }
@@ -221,6 +370,18 @@ impl DaemonClient {
             queue_id: queue_id.to_string(),
             plaintext: plaintext.to_string(),
             peer_encrypt_pk: peer_encrypt_pk.to_string(),
+            wait: false,
+        });
+    }
+
+    /// Like `send_message`, but the daemon answers `MessageSent` only once the
+    /// mailbox accepted it, and `Error` if the post failed.
+    pub fn send_message_and_wait(&self, queue_id: &str, plaintext: &str, peer_encrypt_pk: &str) {
+        self.send_command(DaemonCommand::SendMessage {
+            queue_id: queue_id.to_string(),
+            plaintext: plaintext.to_string(),
+            peer_encrypt_pk: peer_encrypt_pk.to_string(),
+            wait: true,
         });
     }
 
@@ -229,13 +390,56 @@ impl DaemonClient {
     }
 
     pub fn export_contact(&self, name: &str) {
-        self.send_command(DaemonCommand::ExportContact { name: name.to_string() });
+        self.send_command(DaemonCommand::ExportContact { name: name.to_string(), print_only: false });
+    }
+
+    /// Fetch a card for printing, leaving no file or settings behind
+    pub fn print_contact(&self, name: &str) {
+        self.send_command(DaemonCommand::ExportContact { name: name.to_string(), print_only: true });
+    }
+
+    pub fn rename_contact(&self, queue_id: &str, name: &str) {
+        self.send_command(DaemonCommand::RenameContact { queue_id: queue_id.to_string(), name: name.to_string() });
+    }
+
+    pub fn delete_contact(&self, queue_id: &str, purge_history: bool) {
+        self.send_command(DaemonCommand::DeleteContact { queue_id: queue_id.to_string(), purge_history });
+    }
+
+    pub fn block_contact(&self, queue_id: &str, blocked: bool) {
+        self.send_command(DaemonCommand::BlockContact { queue_id: queue_id.to_string(), blocked });
+    }
+
+    pub fn set_contact_notes(&self, queue_id: &str, notes: &str) {
+        self.send_command(DaemonCommand::SetContactNotes { queue_id: queue_id.to_string(), notes: notes.to_string() });
+    }
+
+    pub fn get_safety_number(&self, queue_id: &str) {
+        self.send_command(DaemonCommand::GetSafetyNumber { queue_id: queue_id.to_string() });
+    }
+
+    pub fn load_contact_requests(&self) {
+        self.send_command(DaemonCommand::LoadContactRequests);
     }
 
-    pub fn update_config(&self, server_url: &str, polling_interval_secs: u64) {
+    pub fn accept_contact_request(&self, id: &str) {
+        self.send_command(DaemonCommand::AcceptContactRequest { id: id.to_string() });
+    }
+
+    pub fn reject_contact_request(&self, id: &str, block: bool) {
+        self.send_command(DaemonCommand::RejectContactRequest { id: id.to_string(), block });
+    }
+
+    pub fn verify_contact(&self, queue_id: &str, verified: bool) {
+        self.send_command(DaemonCommand::VerifyContact { queue_id: queue_id.to_string(), verified });
+    }
+
+    pub fn update_config(&self, server_url: &str, polling_interval_secs: u64, polling: &PollingPolicy, public_inbox: bool) {
         self.send_command(DaemonCommand::UpdateConfig {
             server_url: server_url.to_string(),
             polling_interval_secs,
+            polling: polling.clone(),
+            public_inbox,
         });
     }
 
@@ -243,6 +447,15 @@ impl DaemonClient {
         self.send_command(DaemonCommand::ResetPollingInterval);
     }
 
+    pub fn get_status(&self) {
+        self.send_command(DaemonCommand::GetStatus);
+    }
+
+    /// Wait for the next response event. Returns None once the daemon disconnects.
+    pub async fn recv(&mut self) -> Option<DaemonEvent> {
+        self.response_rx.recv().await
+    }
+
     /// Drain any pending response events without blocking.
     /// Returns all events currently in the buffer.
     pub fn try_recv_all(&mut self) -> Vec<DaemonEvent> {
@@ -1,5 +1,11 @@
 pub mod storage;
 pub mod crypto;
+pub mod contact_card;
 pub mod config;
 pub mod mailbox;
+pub mod filesystem;
+pub mod failover;
+pub mod tls;
+pub mod transport;
+pub mod polling_policy;
 pub mod logger;
@@ -10,7 +10,7 @@ static LOG_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
 /// Initialize logger and create session log file
 pub fn init_logger() -> std::io::Result<()> {
     let log_dir = crate::storage::get_app_data_dir()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+        .map_err(std::io::Error::other)?
         .join("logs");
 
     std::fs::create_dir_all(&log_dir)?;
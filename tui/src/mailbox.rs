@@ -1,5 +1,120 @@
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OnceCell};
+
+use crate::tls::TlsConfig;
+use crate::transport::{self, FetchResults, Transport};
+
+// ── Errors ───────────────────────────────────────────────────────────────────
+
+/// Typed failures from a mailbox transport. Local transports report I/O
+/// problems as `Network`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailboxError {
+    /// Couldn't reach the mailbox (DNS, connect, timeout, local I/O)
+    Network(String),
+    /// HTTP 429, or we're still inside a backoff window the server asked for
+    RateLimited { retry_after: Option<Duration> },
+    /// HTTP 5xx
+    Server { status: u16, message: String, retry_after: Option<Duration> },
+    /// HTTP 401/403
+    Auth { status: u16, message: String },
+    /// Anything else unexpected: other 4xx, malformed responses
+    Protocol(String),
+    /// The server's certificate didn't match `Config::tls.spki_pins` (host)
+    PinMismatch(String),
+}
+
+impl MailboxError {
+    /// How long the server asked us to wait, if it did
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } | Self::Server { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MailboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "Network error: {}", e),
+            Self::RateLimited { retry_after: Some(d) } => write!(f, "Rate limited by server (retry in {}s)", d.as_secs()),
+            Self::RateLimited { retry_after: None } => write!(f, "Rate limited by server"),
+            Self::Server { status, message, .. } | Self::Auth { status, message } => {
+                write!(f, "HTTP {}: {}", status, message)
+            }
+            Self::Protocol(e) => write!(f, "{}", e),
+            Self::PinMismatch(host) => {
+                write!(f, "Certificate for {} doesn't match the pinned key — connection refused", host)
+            }
+        }
+    }
+}
+
+impl From<MailboxError> for String {
+    fn from(e: MailboxError) -> Self {
+        e.to_string()
+    }
+}
+
+// ── Backoff ──────────────────────────────────────────────────────────────────
+
+/// First wait after a 429/5xx without `Retry-After`; doubles per failure
+const BACKOFF_BASE: Duration = Duration::from_secs(5);
+
+/// Longest we'll back off on our own
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+/// Longest `Retry-After` we'll honor
+const RETRY_AFTER_MAX: Duration = Duration::from_secs(3600);
+
+/// Shared by every request a client makes, so one 429 quiets them all
+#[derive(Debug, Default)]
+struct Backoff {
+    until: Option<Instant>,
+    failures: u32,
+}
+
+impl Backoff {
+    fn remaining(&self) -> Option<Duration> {
+        self.until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|d| !d.is_zero())
+    }
+
+    /// Start (or extend) a backoff window. Returns its length.
+    fn record_failure(&mut self, retry_after: Option<Duration>) -> Duration {
+        self.failures = self.failures.saturating_add(1);
+        let wait = match retry_after {
+            Some(d) => d.min(RETRY_AFTER_MAX),
+            None => BACKOFF_BASE.saturating_mul(1 << (self.failures - 1).min(10)).min(BACKOFF_MAX),
+        };
+        self.until = Some(Instant::now() + wait);
+        wait
+    }
+
+    fn record_success(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Parse `Retry-After`: delay-seconds or an HTTP-date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(secs as u64))
+}
+
+// ── Protocol types ───────────────────────────────────────────────────────────
 
 #[derive(Debug, Serialize)]
 struct PostMessageRequest {
@@ -13,8 +128,14 @@ pub struct MessageMeta {
     pub filename: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<usize>,
+    /// Hex SHA-256 commitment to the deletion token (see `crypto::derive_delete_token`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_hash: Option<String>,
 }
 
+/// Header carrying the deletion token's preimage on DELETE
+pub const DELETE_TOKEN_HEADER: &str = "X-Delete-Token";
+
 #[derive(Debug, Deserialize)]
 struct PostMessageResponse {
     pub id: String,
@@ -34,25 +155,232 @@ pub struct ServerMessage {
     pub data: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct BatchMessagesResponse {
+    queues: HashMap<String, Vec<ServerMessage>>,
+}
+
+/// Most queues sent in one batch request (matches the reference server's limit)
+const MAX_BATCH_QUEUES: usize = 500;
+
 #[derive(Debug, Deserialize)]
 struct DeleteMessageResponse {
     pub success: bool,
     pub deleted: String,
 }
 
+/// Optional protocol features a server advertises at `GET /capabilities`
+#[derive(Debug, Default, Deserialize)]
+pub struct Capabilities {
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// One server-sent event on a `/subscribe` stream
+#[derive(Debug, Deserialize)]
+struct PushEvent {
+    queue_id: String,
+}
+
+/// A subscription that hears nothing (not even keep-alives) for this long is dead
+const SUBSCRIBE_IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+
+// ── Client ───────────────────────────────────────────────────────────────────
+
+/// Build an HTTP client, optionally routed through a proxy and with custom
+/// TLS trust. `timeout` bounds whole requests; the connect phase is always bounded.
+fn build_http_client(
+    proxy: Option<&str>,
+    tls: Option<&rustls::ClientConfig>,
+    timeout: Option<Duration>,
+) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().connect_timeout(Duration::from_secs(30));
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(tls) = tls {
+        builder = builder.use_preconfigured_tls(tls.clone());
+    }
+
+    if let Some(url) = proxy {
+        let scheme = url.split("://").next().unwrap_or_default().to_ascii_lowercase();
+        if !["http", "https", "socks5", "socks5h"].contains(&scheme.as_str()) {
+            return Err(format!("Unsupported proxy {:?}: use an http, https, socks5 or socks5h URL, or \"tor\"", url));
+        }
+        let proxy = reqwest::Proxy::all(url).map_err(|e| format!("Invalid proxy {:?}: {}", url, e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Wrap a failed request, picking certificate pin mismatches out of the
+/// error chain so they don't pass for ordinary network trouble
+fn request_error(context: &str, e: reqwest::Error) -> MailboxError {
+    let mut source: Option<&dyn std::error::Error> = Some(&e);
+    while let Some(err) = source {
+        if err.to_string().contains(crate::tls::PIN_MISMATCH) {
+            let host = e.url().and_then(|u| u.host_str()).unwrap_or("mailbox server");
+            return MailboxError::PinMismatch(host.to_string());
+        }
+        source = err.source();
+    }
+    MailboxError::Network(format!("{}: {}", context, e))
+}
+
 pub struct MailboxClient {
     base_url: String,
-    client: reqwest::Client,
+    /// Err if the configured proxy or TLS settings are unusable — requests
+    /// then fail rather than silently going out without them
+    client: Result<reqwest::Client, String>,
+    /// Same settings, no overall timeout (for push streams)
+    stream_client: Result<reqwest::Client, String>,
+    capabilities: OnceCell<Capabilities>,
+    backoff: Mutex<Backoff>,
 }
 
 impl MailboxClient {
     pub fn new(base_url: String) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::with_options(base_url, None, &TlsConfig::default())
+    }
+
+    /// Client whose every request goes through `proxy` (see `Config::proxy`)
+    /// and trusts the server per `tls` (see `Config::tls`)
+    pub fn with_options(base_url: String, proxy: Option<&str>, tls: &TlsConfig) -> Self {
+        let proxy = proxy.map(|p| {
+            if p.eq_ignore_ascii_case("tor") { crate::config::TOR_PROXY_URL.to_string() } else { p.to_string() }
+        });
+        let tls = crate::tls::client_config(tls);
+        let build = |timeout| {
+            let tls = tls.clone()?;
+            build_http_client(proxy.as_deref(), tls.as_ref(), timeout)
+        };
+
+        Self {
+            base_url,
+            client: build(Some(Duration::from_secs(30))),
+            stream_client: build(None),
+            capabilities: OnceCell::new(),
+            backoff: Mutex::new(Backoff::default()),
+        }
+    }
+
+    fn client(&self) -> Result<&reqwest::Client, MailboxError> {
+        self.client.as_ref().map_err(|e| MailboxError::Network(e.clone()))
+    }
 
-        Self { base_url, client }
+    /// Time left in the current backoff window, if the server asked us to slow down
+    pub fn backoff_remaining(&self) -> Option<Duration> {
+        self.backoff.lock().ok()?.remaining()
+    }
+
+    /// Fail fast while backing off instead of adding to the server's load
+    fn check_backoff(&self) -> Result<(), MailboxError> {
+        match self.backoff_remaining() {
+            Some(remaining) => Err(MailboxError::RateLimited { retry_after: Some(remaining) }),
+            None => Ok(()),
+        }
+    }
+
+    /// Classify a response: pass successes through, turn failures into typed
+    /// errors, and start a backoff window on 429/5xx
+    async fn check_status(&self, response: reqwest::Response) -> Result<reqwest::Response, MailboxError> {
+        let status = response.status();
+        if status.is_success() {
+            if let Ok(mut backoff) = self.backoff.lock() {
+                backoff.record_success();
+            }
+            return Ok(response);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        let message = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        let code = status.as_u16();
+        let server_unhappy = code == 429 || status.is_server_error();
+        let retry_after = if server_unhappy {
+            self.backoff.lock().ok().map(|mut b| b.record_failure(retry_after))
+        } else {
+            retry_after
+        };
+
+        Err(match code {
+            429 => MailboxError::RateLimited { retry_after },
+            401 | 403 => MailboxError::Auth { status: code, message },
+            _ if status.is_server_error() => MailboxError::Server { status: code, message, retry_after },
+            _ => MailboxError::Protocol(format!("HTTP {}: {}", status, message)),
+        })
+    }
+
+    /// Features the server advertises. Servers without the endpoint (404) support
+    /// none; other failures aren't cached so the next call asks again.
+    pub async fn capabilities(&self) -> Result<&Capabilities, MailboxError> {
+        self.capabilities.get_or_try_init(|| async {
+            self.check_backoff()?;
+            let url = format!("{}/capabilities", self.base_url);
+            let response = self.client()?.get(&url).send().await.map_err(|e| request_error("Failed to reach server", e))?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(Capabilities::default());
+            }
+            let response = self.check_status(response).await?;
+            Ok(response.json().await.unwrap_or_default())
+        }).await
+    }
+
+    /// Open a push subscription for `queue_ids`. The receiver yields a queue id
+    /// whenever a message is posted there, and closes when the stream drops.
+    /// Returns `Ok(None)` if the server doesn't support push.
+    pub async fn subscribe_queues(&self, queue_ids: Vec<String>) -> Result<Option<mpsc::UnboundedReceiver<String>>, MailboxError> {
+        if !self.capabilities().await?.supports("subscribe") {
+            return Ok(None);
+        }
+        self.check_backoff()?;
+
+        // No overall timeout here: the response body is a long-lived stream
+        let client = self.stream_client.as_ref().map_err(|e| MailboxError::Network(e.clone()))?;
+
+        let url = format!("{}/subscribe", self.base_url);
+        let response = client
+            .post(&url)
+            .json(&serde_json::json!({ "queues": queue_ids }))
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to subscribe", e))?;
+        let mut response = self.check_status(response).await?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buf: Vec<u8> = Vec::new();
+            // Stop on close, error, or silence past the keep-alive window
+            while let Ok(Ok(Some(chunk))) = tokio::time::timeout(SUBSCRIBE_IDLE_TIMEOUT, response.chunk()).await {
+                buf.extend_from_slice(&chunk);
+                while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = buf.drain(..=pos).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    let Some(data) = line.trim_end().strip_prefix("data:") else { continue };
+                    if let Ok(event) = serde_json::from_str::<PushEvent>(data.trim()) {
+                        if tx.send(event.queue_id).is_err() {
+                            return; // Subscriber went away
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Some(rx))
     }
 
     /// Send a message to the mailbox server
@@ -61,7 +389,8 @@ impl MailboxClient {
         queue_id: &str,
         encrypted_data: String,
         meta: MessageMeta,
-    ) -> Result<String, String> {
+    ) -> Result<String, MailboxError> {
+        self.check_backoff()?;
         let url = format!("{}/mailbox/{}", self.base_url, queue_id);
 
         let request = PostMessageRequest {
@@ -70,29 +399,21 @@ impl MailboxClient {
         };
 
         let response = self
-            .client
+            .client()?
             .post(&url)
             .json(&request)
             .send()
             .await
-            .map_err(|e| format!("Failed to send message: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("HTTP {}: {}", status, error_text));
-        }
+            .map_err(|e| request_error("Failed to send message", e))?;
+        let response = self.check_status(response).await?;
 
         let result: PostMessageResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .map_err(|e| MailboxError::Protocol(format!("Failed to parse response: {}", e)))?;
 
         if !result.success {
-            return Err("Server reported failure".to_string());
+            return Err(MailboxError::Protocol("Server reported failure".to_string()));
         }
 
         crate::logger::log_to_file(&format!("Message sent at timestamp: {}", result.timestamp));
@@ -100,63 +421,201 @@ impl MailboxClient {
     }
 
     /// Fetch all messages from the mailbox server
-    pub async fn fetch_messages(&self, queue_id: &str) -> Result<Vec<ServerMessage>, String> {
+    pub async fn fetch_messages(&self, queue_id: &str) -> Result<Vec<ServerMessage>, MailboxError> {
+        self.check_backoff()?;
         let url = format!("{}/mailbox/{}", self.base_url, queue_id);
 
         let response = self
-            .client
+            .client()?
             .get(&url)
             .send()
             .await
-            .map_err(|e| format!("Failed to fetch messages: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("HTTP {}: {}", status, error_text));
-        }
+            .map_err(|e| request_error("Failed to fetch messages", e))?;
+        let response = self.check_status(response).await?;
 
         let result: GetMessagesResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .map_err(|e| MailboxError::Protocol(format!("Failed to parse response: {}", e)))?;
 
         Ok(result.messages)
     }
 
-    /// Delete a message from the mailbox server
-    pub async fn delete_message(&self, queue_id: &str, message_id: &str) -> Result<(), String> {
-        let url = format!("{}/mailbox/{}/{}", self.base_url, queue_id, message_id);
+    /// Fetch several queues in one request (servers advertising "batch").
+    /// Queues missing from the response are treated as empty.
+    pub async fn fetch_batch(&self, queue_ids: &[String]) -> Result<HashMap<String, Vec<ServerMessage>>, MailboxError> {
+        self.check_backoff()?;
+        let url = format!("{}/mailbox/batch", self.base_url);
 
         let response = self
-            .client
-            .delete(&url)
+            .client()?
+            .post(&url)
+            .json(&serde_json::json!({ "queues": queue_ids }))
             .send()
             .await
-            .map_err(|e| format!("Failed to delete message: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("HTTP {}: {}", status, error_text));
+            .map_err(|e| request_error("Failed to fetch messages", e))?;
+        let response = self.check_status(response).await?;
+
+        let result: BatchMessagesResponse = response
+            .json()
+            .await
+            .map_err(|e| MailboxError::Protocol(format!("Failed to parse response: {}", e)))?;
+
+        Ok(result.queues)
+    }
+
+    /// Delete a message from the mailbox server, proving the right to with
+    /// `delete_token` (hex) when the message was posted with a commitment
+    pub async fn delete_message(&self, queue_id: &str, message_id: &str, delete_token: Option<&str>) -> Result<(), MailboxError> {
+        self.check_backoff()?;
+        let url = format!("{}/mailbox/{}/{}", self.base_url, queue_id, message_id);
+
+        let mut request = self.client()?.delete(&url);
+        if let Some(token) = delete_token {
+            request = request.header(DELETE_TOKEN_HEADER, token);
         }
 
+        let response = request
+            .send()
+            .await
+            .map_err(|e| request_error("Failed to delete message", e))?;
+        let response = self.check_status(response).await?;
+
         let result: DeleteMessageResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+            .map_err(|e| MailboxError::Protocol(format!("Failed to parse response: {}", e)))?;
 
         if !result.success {
-            return Err("Delete operation reported failure".to_string());
+            return Err(MailboxError::Protocol("Delete operation reported failure".to_string()));
         }
 
         crate::logger::log_to_file(&format!("Successfully deleted message: {}", result.deleted));
         Ok(())
     }
 }
+
+#[async_trait]
+impl Transport for MailboxClient {
+    async fn post(&self, queue_id: &str, data: String, meta: MessageMeta) -> Result<String, MailboxError> {
+        self.send_message(queue_id, data, meta).await
+    }
+
+    async fn fetch(&self, queue_id: &str) -> Result<Vec<ServerMessage>, MailboxError> {
+        self.fetch_messages(queue_id).await
+    }
+
+    async fn fetch_many(&self, queue_ids: &[String]) -> FetchResults {
+        let batch = self.capabilities().await.is_ok_and(|c| c.supports("batch"));
+        if !batch {
+            return transport::fetch_concurrently(self, queue_ids).await;
+        }
+
+        let mut results = Vec::with_capacity(queue_ids.len());
+        for chunk in queue_ids.chunks(MAX_BATCH_QUEUES) {
+            match self.fetch_batch(chunk).await {
+                Ok(mut by_queue) => results.extend(chunk.iter().map(|queue_id| {
+                    (queue_id.clone(), Ok(by_queue.remove(queue_id).unwrap_or_default()))
+                })),
+                // Endpoint missing or misbehaving: fall back to per-queue fetches
+                Err(e @ MailboxError::Protocol(_)) => {
+                    crate::logger::log_to_file(&format!("Batch fetch failed ({}), fetching queues individually", e));
+                    results.extend(transport::fetch_concurrently(self, chunk).await);
+                }
+                // Unreachable or unhappy server: more requests won't help
+                Err(e) => results.extend(chunk.iter().map(|queue_id| (queue_id.clone(), Err(e.clone())))),
+            }
+        }
+        results
+    }
+
+    async fn delete(&self, queue_id: &str, message_id: &str, delete_token: Option<&str>) -> Result<(), MailboxError> {
+        self.delete_message(queue_id, message_id, delete_token).await
+    }
+
+    async fn subscribe(&self, queue_ids: Vec<String>) -> Result<Option<mpsc::UnboundedReceiver<String>>, MailboxError> {
+        self.subscribe_queues(queue_ids).await
+    }
+
+    fn backoff_remaining(&self) -> Option<Duration> {
+        MailboxClient::backoff_remaining(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after_and_backoff() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+
+        let mut backoff = Backoff::default();
+        assert_eq!(backoff.remaining(), None);
+        assert_eq!(backoff.record_failure(None), BACKOFF_BASE);
+        assert_eq!(backoff.record_failure(None), BACKOFF_BASE * 2);
+        assert_eq!(backoff.record_failure(Some(Duration::from_secs(7200))), RETRY_AFTER_MAX);
+        assert!(backoff.remaining().is_some());
+
+        backoff.record_success();
+        assert_eq!(backoff.remaining(), None);
+    }
+
+    /// A local server answering one request per status line, in order
+    async fn serve_statuses(statuses: &'static [&'static str]) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                // Read the whole request so closing doesn't reset the connection
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let body = r#"{"error":"nope"}"#;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_does_not_back_off() {
+        let url = serve_statuses(&["409 Conflict", "503 Service Unavailable"]).await;
+        let client = MailboxClient::new(url);
+        let meta = || MessageMeta { filename: None, size: None, delete_hash: None };
+
+        // One full queue is that request's problem, not the relay's
+        let full = client.send_message("queue1", "aGk=".to_string(), meta()).await;
+        assert!(matches!(full, Err(MailboxError::Protocol(_))));
+        assert_eq!(client.backoff_remaining(), None);
+
+        let down = client.send_message("queue2", "aGk=".to_string(), meta()).await;
+        assert!(matches!(down, Err(MailboxError::Server { status: 503, .. })));
+        assert!(client.backoff_remaining().is_some());
+    }
+}
@@ -3,11 +3,13 @@ mod event;
 mod app;
 mod ui;
 mod ipc;
+mod cli;
 
 // Re-export shared modules from lib so crate:: references in submodules resolve
+pub(crate) use trassenger_lib::contact_card;
 pub(crate) use trassenger_lib::logger;
+pub(crate) use trassenger_lib::polling_policy;
 pub(crate) use trassenger_lib::storage;
-pub(crate) use trassenger_lib::config;
 
 use app::App;
 use crossterm::{
@@ -27,6 +29,20 @@ use std::io;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+
+    // --no-spawn: never start the daemon ourselves (e.g. it's run by a service manager)
+    let no_spawn = args.contains(&"--no-spawn".to_string());
+
+    // One-shot CLI commands (send, peers, history, ...) bypass the TUI entirely
+    if let Some(cli_args) = cli::command_args(&args) {
+        if let Err(e) = storage::init_storage() {
+            eprintln!("Failed to initialize storage: {}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(cli::run(&cli_args, no_spawn).await);
+    }
+
     // Initialize logger (no console output)
     logger::init_logger()?;
 
@@ -40,16 +56,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut event_handler = EventHandler::new();
     event_handler.spawn_keyboard_listener();
 
-    // Connect to daemon
-    let daemon_client = match ipc::DaemonClient::connect(event_handler.sender()).await {
+    // Connect to daemon (starting it if needed)
+    let connected = if no_spawn {
+        ipc::DaemonClient::connect(event_handler.sender()).await
+    } else {
+        ipc::DaemonClient::connect_or_spawn(event_handler.sender()).await
+    };
+    let daemon_client = match connected {
         Ok(client) => client,
         Err(e) => {
             eprintln!("Error: {}", e);
-            eprintln!("Please start the Trassenger daemon first.");
+            if no_spawn {
+                eprintln!("Please start the Trassenger daemon first.");
+            } else {
+                eprintln!("Could not start the Trassenger daemon.");
+            }
             std::process::exit(1);
         }
     };
 
+    daemon_client.hello();
     logger::log_to_file("Connected to daemon");
 
     // Initialize application state (loads from daemon)
@@ -152,7 +178,7 @@ fn render_ui(f: &mut ratatui::Frame, app: &App) {
         };
         let text_lines: u16 = input_text.split('\n').map(|seg| {
             let chars = seg.chars().count();
-            ((chars + content_width - 1) / content_width).max(1) as u16
+            chars.div_ceil(content_width).max(1) as u16
         }).sum();
         let text_lines = text_lines.max(2);
         let max_input = f.area().height / 3;
@@ -177,6 +203,14 @@ fn render_ui(f: &mut ratatui::Frame, app: &App) {
             ui::render_contacts_view(f, app, chunks[0]);
             ui::render_view_hints(f, "Esc to return to chat", chunks[1]);
         }
+        app::MenuState::ContactDetails => {
+            ui::render_contact_details_view(f, app, chunks[0]);
+            ui::render_view_hints(f, "Esc to return to contacts", chunks[1]);
+        }
+        app::MenuState::Requests => {
+            ui::render_requests_view(f, app, chunks[0]);
+            ui::render_view_hints(f, "Esc to return to chat", chunks[1]);
+        }
         app::MenuState::ImportContact => {
             ui::render_import_view(f, app, chunks[0]);
             ui::render_input_area(f, app, chunks[1]);
@@ -0,0 +1,169 @@
+//! How often the daemon checks the mailbox.
+//!
+//! While a TUI is open the interval starts at `Config::polling_interval_secs`
+//! and grows by `backoff_factor` after each empty poll, up to `max_secs`. With
+//! no TUI (or while push delivery is active) the daemon polls every
+//! `background_secs`, stretched to the quiet-hours interval inside that window.
+//! Every wait is randomized by ±`jitter` so clients don't poll in lockstep.
+
+use std::fmt;
+
+use chrono::{Local, NaiveTime};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingPolicy {
+    /// Slowest interval while a TUI is open (seconds)
+    pub max_secs: u64,
+    /// Interval with no TUI open, and the safety-net poll under push (seconds)
+    pub background_secs: u64,
+    /// Multiplier applied to the interval after each poll with nothing new
+    pub backoff_factor: f64,
+    /// Random spread applied to each wait, as a fraction (0.1 = ±10%)
+    pub jitter: f64,
+    /// Low-power window for background polling, e.g. overnight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for PollingPolicy {
+    fn default() -> Self {
+        Self {
+            max_secs: 60,
+            background_secs: 60,
+            backoff_factor: 2.0,
+            jitter: 0.1,
+            quiet_hours: None,
+        }
+    }
+}
+
+impl PollingPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check the policy makes sense alongside the fastest interval `min_secs`
+    pub fn validate(&self, min_secs: u64) -> Result<(), String> {
+        if min_secs == 0 || self.background_secs == 0 {
+            return Err("Intervals must be at least 1 second".to_string());
+        }
+        if self.max_secs < min_secs {
+            return Err(format!("Max interval ({}s) is below the polling interval ({}s)", self.max_secs, min_secs));
+        }
+        if !(1.0..=10.0).contains(&self.backoff_factor) {
+            return Err("Backoff factor must be between 1 and 10".to_string());
+        }
+        if !(0.0..=0.5).contains(&self.jitter) {
+            return Err("Jitter must be between 0 and 0.5".to_string());
+        }
+        if let Some(quiet) = &self.quiet_hours {
+            quiet.window().ok_or("Quiet hours must look like 23:00-07:00")?;
+            if quiet.interval_secs == 0 {
+                return Err("Quiet-hours interval must be at least 1 second".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Background interval right now, honoring quiet hours
+    pub fn background_interval_now(&self) -> u64 {
+        self.background_interval_at(Local::now().time())
+    }
+
+    fn background_interval_at(&self, time: NaiveTime) -> u64 {
+        match &self.quiet_hours {
+            Some(quiet) if quiet.contains(time) => quiet.interval_secs.max(self.background_secs),
+            _ => self.background_secs,
+        }
+    }
+
+    /// `secs` spread randomly by ±jitter (never below one second)
+    pub fn jittered(&self, secs: u64) -> u64 {
+        if self.jitter <= 0.0 {
+            return secs;
+        }
+        let spread = rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+        ((secs as f64) * (1.0 + spread)).round().max(1.0) as u64
+    }
+}
+
+/// Daily window (local time) in which background polling slows down.
+/// Written as `23:00-07:00 900`: start, end, and the interval in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"; before `start` means the window runs past midnight
+    pub end: String,
+    pub interval_secs: u64,
+}
+
+impl QuietHours {
+    /// Parse `23:00-07:00 900`; empty or `off` means no quiet hours
+    pub fn parse(input: &str) -> Result<Option<Self>, String> {
+        let input = input.trim();
+        if input.is_empty() || input.eq_ignore_ascii_case("off") {
+            return Ok(None);
+        }
+
+        let usage = || "Quiet hours must look like 23:00-07:00 900 (or off)".to_string();
+        let (window, interval) = input.split_once(' ').ok_or_else(usage)?;
+        let (start, end) = window.split_once('-').ok_or_else(usage)?;
+        let quiet = Self {
+            start: start.trim().to_string(),
+            end: end.trim().to_string(),
+            interval_secs: interval.trim().trim_end_matches('s').parse().map_err(|_| usage())?,
+        };
+        quiet.window().ok_or_else(usage)?;
+        Ok(Some(quiet))
+    }
+
+    fn window(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let parse = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").ok();
+        Some((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        match self.window() {
+            Some((start, end)) if start <= end => start <= time && time < end,
+            Some((start, end)) => time >= start || time < end,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} {}", self.start, self.end, self.interval_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours_wrap_midnight() {
+        let quiet = QuietHours::parse("23:00-07:00 900").unwrap().unwrap();
+        assert_eq!(quiet.to_string(), "23:00-07:00 900");
+        assert!(QuietHours::parse("off").unwrap().is_none());
+        assert!(QuietHours::parse("23:00 900").is_err());
+        assert!(QuietHours::parse("25:00-07:00 900").is_err());
+
+        let policy = PollingPolicy { quiet_hours: Some(quiet), ..PollingPolicy::default() };
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(policy.background_interval_at(at(23, 30)), 900);
+        assert_eq!(policy.background_interval_at(at(6, 59)), 900);
+        assert_eq!(policy.background_interval_at(at(7, 0)), 60);
+        assert_eq!(policy.background_interval_at(at(12, 0)), 60);
+
+        for _ in 0..100 {
+            assert!((54..=66).contains(&policy.jittered(60)));
+        }
+        assert!(policy.validate(5).is_ok());
+        assert!(policy.validate(120).is_err(), "max below the polling interval");
+    }
+}
@@ -1,4 +1,8 @@
+use crate::contact_card::ContactCard;
 use crate::crypto::Keypair;
+use crate::polling_policy::PollingPolicy;
+use crate::tls::TlsConfig;
+use crate::transport::TransportConfig;
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -7,7 +11,108 @@ use std::path::PathBuf;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server_url: String,
+    /// Mailbox servers to fail over to, in order, when `server_url` is down
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_servers: Vec<String>,
+    /// Fastest polling interval while a TUI is open
     pub polling_interval_secs: u64,
+    /// Backoff, background interval, jitter and quiet hours
+    #[serde(default, skip_serializing_if = "PollingPolicy::is_default")]
+    pub polling: PollingPolicy,
+    /// Port for the daemon's loopback HTTP API (disabled when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_port: Option<u16>,
+    /// Commands the daemon runs when events happen
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+    /// How messages are delivered (HTTP mailbox by default)
+    #[serde(default, skip_serializing_if = "TransportConfig::is_default")]
+    pub transport: TransportConfig,
+    /// Proxy for all mailbox traffic: an `http://`, `https://`, `socks5://` or
+    /// `socks5h://` URL, or `tor` for a local Tor client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Extra CA certificates and/or SPKI pins for the mailbox server
+    #[serde(default, skip_serializing_if = "TlsConfig::is_empty")]
+    pub tls: TlsConfig,
+    /// Name on the card we send back when accepting an invite; remembered
+    /// from the last export
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Read our public inbox, where people who hold our card but aren't
+    /// contacts yet can send a first message
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub public_inbox: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_url: crate::config::DEFAULT_SERVER_URL.to_string(),
+            fallback_servers: Vec::new(),
+            polling_interval_secs: crate::config::DEFAULT_POLLING_INTERVAL,
+            polling: PollingPolicy::default(),
+            api_port: None,
+            hooks: HooksConfig::default(),
+            transport: TransportConfig::default(),
+            proxy: None,
+            tls: TlsConfig::default(),
+            display_name: None,
+            public_inbox: false,
+        }
+    }
+}
+
+impl Config {
+    /// Every mailbox server we use, most preferred first
+    pub fn server_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.server_url.clone()];
+        for url in &self.fallback_servers {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
+}
+
+/// User hook commands, run through the system shell with a JSON
+/// description of the event on stdin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_message: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_send_failed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_contact_imported: Vec<String>,
+    /// Seconds before a running hook is killed
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout() -> u64 {
+    crate::config::DEFAULT_HOOK_TIMEOUT
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            on_message: Vec::new(),
+            on_send_failed: Vec::new(),
+            on_contact_imported: Vec::new(),
+            timeout_secs: default_hook_timeout(),
+        }
+    }
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.on_message.is_empty()
+            && self.on_send_failed.is_empty()
+            && self.on_contact_imported.is_empty()
+            && self.timeout_secs == default_hook_timeout()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +121,50 @@ pub struct Peer {
     pub encrypt_pk: String,
     pub sign_pk: String,
     pub queue_id: String,
+    /// Mailbox servers the contact reads from, from their contact card.
+    /// Empty means they use ours.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mailboxes: Vec<String>,
+    /// Blocked contacts' queue isn't polled and we don't send to them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+    /// Safety number confirmed out of band
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+    /// When a re-import replaced the keys we first saw (unix seconds);
+    /// cleared once the contact is verified again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_changed_at: Option<i64>,
+    /// Free-form notes about the contact
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Their card advertised a public inbox: until they write back, our
+    /// messages go there so they arrive even if we aren't added yet
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inbox: bool,
+}
+
+/// One-time queue named in an invite we handed out, where the invitee
+/// posts their card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rendezvous {
+    pub queue_id: String,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+/// A contact card that arrived at a rendezvous queue or our public inbox,
+/// waiting for approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactRequest {
+    pub id: String,
+    pub card: ContactCard,
+    /// Unix seconds
+    pub received_at: i64,
+    /// First message, for requests from our public inbox. It and any that
+    /// follow are already stored under the conversation they'd start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,16 +273,21 @@ pub fn load_config() -> Result<Config, String> {
 pub fn save_peer(peer: &Peer) -> Result<(), String> {
     let mut peers = load_peers()?;
 
-    // Remove existing peer with same name
-    peers.retain(|p| p.name != peer.name);
+    // Replace the existing entry for this key, keeping its place in the list
+    match peers.iter_mut().find(|p| p.encrypt_pk == peer.encrypt_pk) {
+        Some(existing) => *existing = peer.clone(),
+        None => peers.push(peer.clone()),
+    }
 
-    // Add new peer
-    peers.push((*peer).clone());
+    save_peers(&peers)
+}
 
+/// Overwrite the contact list
+pub fn save_peers(peers: &[Peer]) -> Result<(), String> {
     let app_dir = get_app_data_dir()?;
     let peers_path = app_dir.join("peers.json");
 
-    let json = serde_json::to_string_pretty(&peers)
+    let json = serde_json::to_string_pretty(peers)
         .map_err(|e| format!("Failed to serialize peers: {}", e))?;
 
     fs::write(peers_path, json)
@@ -158,6 +312,47 @@ pub fn load_peers() -> Result<Vec<Peer>, String> {
         .map_err(|e| format!("Failed to parse peers: {}", e))
 }
 
+/// Rendezvous queues from invites we've handed out
+pub fn load_rendezvous() -> Result<Vec<Rendezvous>, String> {
+    load_json_list("rendezvous.json")
+}
+
+pub fn save_rendezvous(entries: &[Rendezvous]) -> Result<(), String> {
+    save_json_list("rendezvous.json", entries)
+}
+
+/// Contact requests waiting for approval
+pub fn load_contact_requests() -> Result<Vec<ContactRequest>, String> {
+    load_json_list("contact_requests.json")
+}
+
+pub fn save_contact_requests(requests: &[ContactRequest]) -> Result<(), String> {
+    save_json_list("contact_requests.json", requests)
+}
+
+fn load_json_list<T: serde::de::DeserializeOwned>(file: &str) -> Result<Vec<T>, String> {
+    let path = get_app_data_dir()?.join(file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse {}: {}", file, e))
+}
+
+fn save_json_list<T: Serialize>(file: &str, items: &[T]) -> Result<(), String> {
+    let path = get_app_data_dir()?.join(file);
+
+    let json = serde_json::to_string_pretty(items)
+        .map_err(|e| format!("Failed to serialize {}: {}", file, e))?;
+
+    fs::write(path, json)
+        .map_err(|e| format!("Failed to write {}: {}", file, e))
+}
+
 /// Initialize SQLite database for messages
 pub fn init_message_db() -> Result<Connection, String> {
     let app_dir = get_app_data_dir()?;
@@ -230,3 +425,21 @@ pub fn load_messages_for_queue(conn: &Connection, queue_id: &str) -> Result<Vec<
 
     Ok(messages)
 }
+
+/// Move a conversation's history to another queue
+pub fn move_messages_to_queue(conn: &Connection, from_queue_id: &str, to_queue_id: &str) -> Result<usize, String> {
+    conn.execute("UPDATE messages SET queue_id = ?2 WHERE queue_id = ?1", [from_queue_id, to_queue_id])
+        .map_err(|e| format!("Failed to move messages: {}", e))
+}
+
+/// Whether the contact on `queue_id` has ever written to us
+pub fn has_inbound_messages(conn: &Connection, queue_id: &str) -> Result<bool, String> {
+    conn.query_row("SELECT EXISTS(SELECT 1 FROM messages WHERE queue_id = ?1 AND is_outbound = 0)", [queue_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query messages: {}", e))
+}
+
+/// Delete a conversation's history; returns how many messages were removed
+pub fn delete_messages_for_queue(conn: &Connection, queue_id: &str) -> Result<usize, String> {
+    conn.execute("DELETE FROM messages WHERE queue_id = ?1", [queue_id])
+        .map_err(|e| format!("Failed to delete messages: {}", e))
+}
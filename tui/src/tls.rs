@@ -0,0 +1,201 @@
+//! TLS trust settings for self-hosted mailboxes.
+//!
+//! `Config::tls` can add CA certificates on top of the system roots, pin the
+//! server's public key (SPKI SHA-256), or both. With pins only, the server's
+//! own certificate must carry a pinned key, which is then trusted on its own —
+//! enough for a self-signed relay. With CAs and pins, the chain must validate
+//! *and* contain a pinned key. When nothing is
+//! configured the platform TLS stack is used as before.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use base64::{Engine as _, engine::general_purpose};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Marker in the handshake error when no certificate matched a pin
+pub const PIN_MISMATCH: &str = "server certificate does not match any pinned SPKI hash";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Extra trusted CA certificates (PEM files)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certs: Vec<String>,
+    /// Accepted server keys: base64 SHA-256 of the certificate's
+    /// SubjectPublicKeyInfo, optionally prefixed `sha256/`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spki_pins: Vec<String>,
+}
+
+impl TlsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.ca_certs.is_empty() && self.spki_pins.is_empty()
+    }
+}
+
+/// Build a rustls config for `tls`, or None when nothing is configured
+pub fn client_config(tls: &TlsConfig) -> Result<Option<ClientConfig>, String> {
+    if tls.is_empty() {
+        return Ok(None);
+    }
+
+    let pins = tls.spki_pins.iter().map(|p| parse_pin(p)).collect::<Result<Vec<_>, _>>()?;
+
+    // Pins alone replace chain validation; otherwise validate against system + extra roots
+    let webpki = if tls.ca_certs.is_empty() {
+        None
+    } else {
+        let mut roots = RootCertStore::empty();
+        let native = rustls_native_certs::load_native_certs().unwrap_or_default();
+        roots.add_parsable_certificates(&native.into_iter().map(|c| c.0).collect::<Vec<_>>());
+        for cert in load_ca_certs(&tls.ca_certs)? {
+            roots.add(&cert).map_err(|e| format!("Invalid CA certificate: {}", e))?;
+        }
+        Some(WebPkiVerifier::new(roots, None))
+    };
+
+    let verifier = PinnedVerifier { webpki, pins };
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(Some(config))
+}
+
+fn load_ca_certs(paths: &[String]) -> Result<Vec<Certificate>, String> {
+    let mut certs = Vec::new();
+    for path in paths {
+        let path = shellexpand::tilde(path).into_owned();
+        let pem = std::fs::read(&path).map_err(|e| format!("Failed to read CA file {}: {}", path, e))?;
+        let found = rustls_pemfile::certs(&mut pem.as_slice())
+            .map_err(|e| format!("Failed to parse CA file {}: {}", path, e))?;
+        if found.is_empty() {
+            return Err(format!("No certificates found in {}", path));
+        }
+        certs.extend(found.into_iter().map(Certificate));
+    }
+    Ok(certs)
+}
+
+fn parse_pin(pin: &str) -> Result<[u8; 32], String> {
+    let encoded = pin.trim().trim_start_matches("sha256/");
+    general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("Invalid SPKI pin {:?}: expected base64 SHA-256", pin))
+}
+
+/// SHA-256 of a DER certificate's SubjectPublicKeyInfo
+pub fn spki_sha256(cert_der: &[u8]) -> Option<[u8; 32]> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert_der).ok()?;
+    Some(Sha256::digest(cert.tbs_certificate.subject_pki.raw).into())
+}
+
+struct PinnedVerifier {
+    webpki: Option<WebPkiVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // Without chain validation the intermediates are just whatever the
+        // server sent, so only its own certificate can match a pin
+        let candidates = match &self.webpki {
+            Some(webpki) => {
+                webpki.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+                intermediates
+            }
+            None => &[],
+        };
+
+        if !self.pins.is_empty() {
+            let matched = std::iter::once(end_entity)
+                .chain(candidates)
+                .filter_map(|cert| spki_sha256(&cert.0))
+                .any(|hash| self.pins.contains(&hash));
+            if !matched {
+                return Err(rustls::Error::General(PIN_MISMATCH.to_string()));
+            }
+        }
+
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed P-256 certificate; pin from
+    // `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64`
+    const CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBhDCCASmgAwIBAgIUO7tNCX6XcaMJ34OaiEswty328RkwCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMbWFpbGJveC50ZXN0MB4XDTI2MTAxODEzNTAyOVoXDTM2MTAx
+NTEzNTAyOVowFzEVMBMGA1UEAwwMbWFpbGJveC50ZXN0MFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAElhswNkmWyF2ZrADUIE9yOViS+ua/OnDWJbea9re7XpI0d+/r
+8x1xp8ZGZ8fkq/emyyFZf8gUPoN8JJ69aV8rIaNTMFEwHQYDVR0OBBYEFD6fRidS
+jZCQAGfd0WmwWN2tBVa7MB8GA1UdIwQYMBaAFD6fRidSjZCQAGfd0WmwWN2tBVa7
+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIhAO7JYZcm/kyx4doV
+H2C6h/HPf22rLEXCAD+cYdJK9zOiAiEA5tw5GzZEy9zlGTbhLaXFek4ThSw/FyWL
+7/vNoy4PjdQ=
+-----END CERTIFICATE-----
+";
+    const PIN: &str = "sha256/bygAweuz8vYLmI+aRbswtrAcHyyFsgtY7vo/NK0+Q3g=";
+
+    // Unrelated certificate for `localhost`
+    const OTHER_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBoDCCAUagAwIBAgIUFChaI3PATXVKMJXVh/dBOeNhePYwCgYIKoZIzj0EAwIw
+EjEQMA4GA1UEAwwHVGVzdCBDQTAeFw0yNjEwMTgxMzU0MzhaFw0yNjExMTcxMzU0
+MzhaMBQxEjAQBgNVBAMMCWxvY2FsaG9zdDBZMBMGByqGSM49AgEGCCqGSM49AwEH
+A0IABJX6jcmNZlriJlgCIwwGrtlLrcn7P31REX+M3/B/APqRNwnWSoJ1GXGaJImT
+r/qIpFaXiZ7acu+gL3knu0QQDX+jeDB2MBQGA1UdEQQNMAuCCWxvY2FsaG9zdDAJ
+BgNVHRMEAjAAMBMGA1UdJQQMMAoGCCsGAQUFBwMBMB0GA1UdDgQWBBRO/3jyG+JC
+5VkbUzg19mFSm9OfaTAfBgNVHSMEGDAWgBQfnqFUE5bGKEZLLktV8ddwnSK4YzAK
+BggqhkjOPQQDAgNIADBFAiEA53IpXdsN6xT+Mgh3XqLKhzY1VIH3kU5OBQUpwd2/
+jDoCIFvJzUy47WnzCfEYPE3C1s6SiS0CbuWFxJlXAMFuOQh5
+-----END CERTIFICATE-----
+";
+
+    fn cert(pem: &str) -> Certificate {
+        Certificate(rustls_pemfile::certs(&mut pem.as_bytes()).unwrap().remove(0))
+    }
+
+    #[test]
+    fn test_spki_hash_matches_pin() {
+        let der = rustls_pemfile::certs(&mut CERT_PEM.as_bytes()).unwrap().remove(0);
+        let pin = parse_pin(PIN).unwrap();
+        assert_eq!(spki_sha256(&der), Some(pin));
+        assert_eq!(parse_pin(PIN.trim_start_matches("sha256/")).unwrap(), pin, "prefix is optional");
+
+        assert!(parse_pin("sha256/dG9vc2hvcnQ=").is_err());
+        assert!(parse_pin("not base64!").is_err());
+        let bad = TlsConfig { ca_certs: vec![], spki_pins: vec!["nope".to_string()] };
+        assert!(client_config(&bad).is_err());
+        assert!(client_config(&TlsConfig::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pins_only_ignores_appended_intermediates() {
+        let verifier = PinnedVerifier { webpki: None, pins: vec![parse_pin(PIN).unwrap()] };
+        let name = ServerName::try_from("mailbox.test").unwrap();
+        let verify = |end_entity: &Certificate, intermediates: &[Certificate]| {
+            verifier.verify_server_cert(end_entity, intermediates, &name, &mut std::iter::empty(), &[], SystemTime::now())
+        };
+
+        assert!(verify(&cert(CERT_PEM), &[]).is_ok());
+        // A MITM can't borrow the pinned certificate as a fake intermediate
+        assert!(verify(&cert(OTHER_PEM), &[cert(CERT_PEM)]).is_err());
+    }
+}
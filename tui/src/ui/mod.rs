@@ -7,6 +7,8 @@ pub use simple::{
     render_hints,
     render_view_hints,
     render_contacts_view,
+    render_contact_details_view,
+    render_requests_view,
     render_import_view,
     render_export_view,
     render_settings_view,
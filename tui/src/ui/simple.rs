@@ -1,4 +1,4 @@
-use crate::app::{App, InputMode, MenuState};
+use crate::app::{App, ContactAction, InputMode, MenuState, SETTINGS_AUTOSTART_FIELD, SETTINGS_INBOX_FIELD};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -14,10 +14,20 @@ pub fn render_message_list(f: &mut Frame, app: &App, area: Rect) {
         let peer = &app.peers[app.selected_peer_index];
 
         // Render header with contact name - clear visual indicator
-        let header = Line::from(vec![
+        let mut header = Line::from(vec![
             Span::styled("Chat: ", Style::default().fg(Color::DarkGray)),
             Span::styled(&peer.name, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         ]);
+        if peer.key_changed_at.is_some() {
+            header.spans.push(Span::styled("  ⚠ key changed", Style::default().fg(Color::Yellow)));
+        } else if peer.verified {
+            header.spans.push(Span::styled("  ✓ verified", Style::default().fg(Color::Green)));
+        } else {
+            header.spans.push(Span::styled("  unverified", Style::default().fg(Color::DarkGray)));
+        }
+        if peer.blocked {
+            header.spans.push(Span::styled("  blocked", Style::default().fg(Color::Red)));
+        }
 
         let header_area = Rect { x: area.x, y: area.y, width: area.width, height: 1 };
         f.render_widget(Paragraph::new(header), header_area);
@@ -64,7 +74,7 @@ fn render_messages_content(f: &mut Frame, app: &App, area: Rect) {
         let mut rows = 0usize;
         for segment in msg.content.split('\n') {
             let chars = segment.chars().count();
-            rows += ((chars + content_width - 1) / content_width).max(1);
+            rows += chars.div_ceil(content_width).max(1);
         }
         MsgMeta { rows }
     }).collect();
@@ -310,9 +320,7 @@ pub fn render_hints(f: &mut Frame, app: &App, area: Rect) {
                         Span::styled("Esc", Style::default().fg(Color::DarkGray)),
                         Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
                     ]),
-                    Line::from(vec![
-                        Span::styled(&app.status_message, Style::default().fg(Color::White)),
-                    ]),
+                    status_line(app),
                 ]
             } else if app.input_mode == InputMode::Editing {
                 // Editing mode in chat - show appropriate newline key
@@ -330,9 +338,7 @@ pub fn render_hints(f: &mut Frame, app: &App, area: Rect) {
                         Span::styled("Esc", Style::default().fg(Color::DarkGray)),
                         Span::styled(" cancel", Style::default().fg(Color::DarkGray)),
                     ]),
-                    Line::from(vec![
-                        Span::styled(&app.status_message, Style::default().fg(Color::White)),
-                    ]),
+                    status_line(app),
                 ]
             } else {
                 // Normal mode in chat
@@ -350,22 +356,69 @@ pub fn render_hints(f: &mut Frame, app: &App, area: Rect) {
                             Style::default().fg(Color::DarkGray)
                         ),
                     ]),
-                    Line::from(vec![
-                        Span::styled(&app.status_message, Style::default().fg(Color::White)),
-                    ]),
+                    status_line(app),
                 ]
             }
         }
-        _ => {
-            // Any other view
+        MenuState::Contacts if app.input_mode == InputMode::Normal && app.contact_action.is_none() => {
             vec![
                 Line::from(vec![
+                    Span::styled("Enter", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" open  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("i", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" details  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("r", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" rename  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("n", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" notes  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("b", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" block  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("d", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" delete  ", Style::default().fg(Color::DarkGray)),
                     Span::styled("Esc", Style::default().fg(Color::DarkGray)),
                     Span::styled(" back to chat", Style::default().fg(Color::DarkGray)),
                 ]),
+                status_line(app),
+            ]
+        }
+        MenuState::Requests => {
+            vec![
                 Line::from(vec![
-                    Span::styled(&app.status_message, Style::default().fg(Color::White)),
+                    Span::styled("↑↓", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" select  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("a", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" accept  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("x", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" reject  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("b", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" block  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("Esc", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" back to chat", Style::default().fg(Color::DarkGray)),
+                ]),
+                status_line(app),
+            ]
+        }
+        MenuState::ContactDetails => {
+            vec![
+                Line::from(vec![
+                    Span::styled("v", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" toggle verified  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("Ctrl+P/N", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" contact  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("Esc", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" back to contacts", Style::default().fg(Color::DarkGray)),
+                ]),
+                status_line(app),
+            ]
+        }
+        _ => {
+            // Any other view
+            vec![
+                Line::from(vec![
+                    Span::styled("Esc", Style::default().fg(Color::DarkGray)),
+                    Span::styled(" back to chat", Style::default().fg(Color::DarkGray)),
                 ]),
+                status_line(app),
             ]
         }
     };
@@ -374,6 +427,23 @@ pub fn render_hints(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Second hint line: a certificate warning (stays until the daemon clears it)
+/// followed by the latest status message
+fn status_line(app: &App) -> Line<'_> {
+    let mut spans = Vec::new();
+    if let Some(warning) = &app.certificate_warning {
+        spans.push(Span::styled(
+            format!("⚠ {}", warning),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+        if !app.status_message.is_empty() {
+            spans.push(Span::styled("  │ ", Style::default().fg(Color::DarkGray)));
+        }
+    }
+    spans.push(Span::styled(&app.status_message, Style::default().fg(Color::White)));
+    Line::from(spans)
+}
+
 /// Render full-screen contacts view
 pub fn render_contacts_view(f: &mut Frame, app: &App, area: Rect) {
     let mut lines = vec![
@@ -393,17 +463,170 @@ pub fn render_contacts_view(f: &mut Frame, app: &App, area: Rect) {
             } else {
                 ("  ", Style::default().fg(Color::White))
             };
-            lines.push(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(prefix, style),
                 Span::styled(&peer.name, style),
+            ];
+            if peer.verified {
+                spans.push(Span::styled(" ✓", Style::default().fg(Color::Green)));
+            }
+            if peer.key_changed_at.is_some() {
+                spans.push(Span::styled("  ⚠ key changed", Style::default().fg(Color::Yellow)));
+            }
+            if peer.blocked {
+                spans.push(Span::styled("  blocked", Style::default().fg(Color::Red)));
+            }
+            if !peer.notes.is_empty() {
+                spans.push(Span::styled(format!("  {}", peer.notes), Style::default().fg(Color::DarkGray)));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let editing = match app.contact_action {
+            Some(ContactAction::Rename) => Some("Name"),
+            Some(ContactAction::Notes) => Some("Notes"),
+            _ => None,
+        };
+        if let Some(label) = editing {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", label), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{}█", app.contact_edit_input), Style::default().fg(Color::Cyan)),
             ]));
         }
     }
 
+    if !app.contact_requests.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("{} contact request(s) waiting - type /requests to review", app.contact_requests.len()),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
     let paragraph = Paragraph::new(lines);
     f.render_widget(paragraph, area);
 }
 
+/// Render cards that answered our invites, waiting to be accepted or rejected
+pub fn render_requests_view(f: &mut Frame, app: &App, area: Rect) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::DarkGray));
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Contact Requests", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+    ];
+
+    if app.contact_requests.is_empty() {
+        lines.push(Line::from(label("No pending requests")));
+        lines.push(Line::from(""));
+        lines.push(Line::from(label("People who answer your invites, or write to your public inbox, show up here for you to approve")));
+        f.render_widget(Paragraph::new(lines), area);
+        return;
+    }
+
+    for (idx, request) in app.contact_requests.iter().enumerate() {
+        let (prefix, style) = if idx == app.selected_request_index {
+            ("→ ", Style::default().fg(Color::Cyan))
+        } else {
+            ("  ", Style::default().fg(Color::White))
+        };
+        let when = chrono::DateTime::from_timestamp(request.received_at, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let kind = if request.message.is_some() { "message request" } else { "answered your invite" };
+        lines.push(Line::from(vec![
+            Span::styled(prefix, style),
+            Span::styled(&request.card.name, style),
+            Span::styled(format!("  {}  {}", kind, when), Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+
+    if let Some(request) = app.contact_requests.get(app.selected_request_index) {
+        let card = &request.card;
+        let mailboxes = if card.mailboxes.is_empty() { "same as yours".to_string() } else { card.mailboxes.join(", ") };
+        lines.push(Line::from(""));
+        if let Some(message) = &request.message {
+            lines.push(Line::from(vec![label("Message:         "), Span::raw(message)]));
+        }
+        lines.push(Line::from(vec![label("Card:            "), Span::styled("✓ Signed by its key", Style::default().fg(Color::Green))]));
+        lines.push(Line::from(vec![label("Mailboxes:       "), Span::raw(mailboxes)]));
+        lines.push(Line::from(vec![label("Signing key:     "), Span::raw(&card.sign_pk)]));
+        lines.push(Line::from(vec![label("Encryption key:  "), Span::raw(&card.encrypt_pk)]));
+        if app.peers.iter().any(|p| p.name.eq_ignore_ascii_case(&card.name)) {
+            lines.push(Line::from(Span::styled(
+                format!("⚠ You already have a contact named {} - accepting replaces their keys", card.name),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        lines.push(Line::from(""));
+        if request.message.is_some() {
+            lines.push(Line::from(label("Sent to your public inbox by someone holding your card. Accept to add them and see the conversation.")));
+        } else {
+            lines.push(Line::from(label("Only someone holding your invite could send this. Accept to add them as a contact.")));
+        }
+    }
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
+/// Render the selected contact's keys, safety number and verification state
+pub fn render_contact_details_view(f: &mut Frame, app: &App, area: Rect) {
+    let Some(peer) = app.peers.get(app.selected_peer_index) else {
+        f.render_widget(Paragraph::new(Span::styled("No contact selected", Style::default().fg(Color::DarkGray))), area);
+        return;
+    };
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::DarkGray));
+
+    let status = if peer.verified {
+        Span::styled("✓ Verified", Style::default().fg(Color::Green))
+    } else {
+        Span::styled("Not verified", Style::default().fg(Color::Yellow))
+    };
+    let mailboxes = if peer.mailboxes.is_empty() { "same as yours".to_string() } else { peer.mailboxes.join(", ") };
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(&peer.name, Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(vec![label("Status:          "), status]),
+    ];
+    if let Some(changed) = peer.key_changed_at.and_then(|t| chrono::DateTime::from_timestamp(t, 0)) {
+        let when = changed.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        lines.push(Line::from(vec![
+            label("                 "),
+            Span::styled(format!("⚠ Keys changed {} - check the safety number again", when), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    if peer.blocked {
+        lines.push(Line::from(vec![label("                 "), Span::styled("Blocked", Style::default().fg(Color::Red))]));
+    }
+    if !peer.notes.is_empty() {
+        lines.push(Line::from(vec![label("Notes:           "), Span::raw(&peer.notes)]));
+    }
+    lines.push(Line::from(vec![label("Mailboxes:       "), Span::raw(mailboxes)]));
+    lines.push(Line::from(vec![label("Signing key:     "), Span::raw(&peer.sign_pk)]));
+    lines.push(Line::from(vec![label("Encryption key:  "), Span::raw(&peer.encrypt_pk)]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Safety number", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))));
+    lines.push(Line::from(""));
+
+    match &app.safety_number {
+        Some((queue_id, groups)) if *queue_id == peer.queue_id => {
+            for row in groups.chunks(4) {
+                lines.push(Line::from(Span::styled(format!("  {}", row.join("  ")), Style::default().fg(Color::Cyan))));
+            }
+        }
+        _ => lines.push(Line::from(label("  Loading..."))),
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(label("Compare this number with the contact in person or over a channel you trust.")));
+    lines.push(Line::from(label("If it matches what they see, press v to mark them verified.")));
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
 /// Render full-screen import view
 pub fn render_import_view(f: &mut Frame, _app: &App, area: Rect) {
     let lines = vec![
@@ -411,7 +634,7 @@ pub fn render_import_view(f: &mut Frame, _app: &App, area: Rect) {
         Line::from(Span::styled("Import Contact", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from(Span::styled("• Drag and drop a .json file here", Style::default().fg(Color::Cyan))),
-        Line::from(Span::styled("• Paste contact JSON", Style::default().fg(Color::DarkGray))),
+        Line::from(Span::styled("• Paste an invite code (trassenger1…) or contact JSON", Style::default().fg(Color::DarkGray))),
         Line::from(Span::styled("• Type file path (e.g., contact-Bob.json)", Style::default().fg(Color::DarkGray))),
         Line::from(""),
         Line::from(Span::styled("Then press Enter to import", Style::default().fg(Color::DarkGray))),
@@ -432,29 +655,70 @@ pub fn render_export_view(f: &mut Frame, app: &App, area: Rect) {
             Line::from(""),
             Line::from(Span::styled("Enter your name and press Enter", Style::default().fg(Color::DarkGray))),
             Line::from(""),
-            Line::from(Span::styled("File will be saved to ~/Downloads/contact-<name>.json", Style::default().fg(Color::DarkGray))),
+            Line::from(Span::styled("File will be saved to ~/Downloads/contact-<name>.json if that folder exists", Style::default().fg(Color::DarkGray))),
             Line::from(""),
         ]
     } else {
-        // Step 2: Show success and file location
-        vec![
+        // Step 2: Show the invite code (and its QR code when it fits) and the file location
+        let mut lines = vec![
             Line::from(""),
             Line::from(Span::styled("Export Contact", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
             Line::from(""),
-            Line::from(Span::styled("✓ Contact file saved to Downloads!", Style::default().fg(Color::Green))),
-            Line::from(""),
-            Line::from(Span::styled("Check your Downloads folder", Style::default().fg(Color::Cyan))),
+            match &app.contact_export_path {
+                Some(path) => Line::from(Span::styled(format!("✓ Contact file saved to {}", path), Style::default().fg(Color::Green))),
+                None => Line::from(Span::styled("No ~/Downloads folder, so no file was saved", Style::default().fg(Color::Yellow))),
+            },
             Line::from(""),
-            Line::from(Span::styled("Share this file with your contact", Style::default().fg(Color::DarkGray))),
+            Line::from(Span::styled("Share this invite code, the QR code or the file with your contact:", Style::default().fg(Color::DarkGray))),
+            Line::from(Span::styled("Once they import it, they show up in /requests for you to accept.", Style::default().fg(Color::DarkGray))),
             Line::from(""),
-            Line::from(Span::styled("Press Esc to return", Style::default().fg(Color::DarkGray))),
-        ]
+        ];
+
+        // The invite wraps over as many rows as it needs
+        let width = area.width.max(1) as usize;
+        for chunk in app.contact_export_invite.as_bytes().chunks(width) {
+            lines.push(Line::from(Span::styled(String::from_utf8_lossy(chunk).into_owned(), Style::default().fg(Color::Cyan))));
+        }
+        lines.push(Line::from(""));
+
+        let qr = invite_qr_lines(&app.contact_export_invite);
+        let fits = lines.len() + qr.len() + 2 <= area.height as usize && qr.first().is_some_and(|row| row.chars().count() <= width);
+        if fits {
+            for row in qr {
+                lines.push(Line::from(Span::styled(row, Style::default().fg(Color::White).bg(Color::Black))));
+            }
+            lines.push(Line::from(""));
+        } else if !qr.is_empty() {
+            lines.push(Line::from(Span::styled("Enlarge the terminal to show a QR code", Style::default().fg(Color::DarkGray))));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled("c copy invite code  Esc return", Style::default().fg(Color::DarkGray))));
+        lines
     };
 
     let paragraph = Paragraph::new(lines);
     f.render_widget(paragraph, area);
 }
 
+/// An invite code as a QR code drawn in half blocks, light modules on a dark
+/// background. Uppercase lets the QR use its compact alphanumeric mode.
+fn invite_qr_lines(invite: &str) -> Vec<String> {
+    use qrcode::render::unicode::Dense1x2;
+    use qrcode::{EcLevel, QrCode};
+
+    match QrCode::with_error_correction_level(invite.to_uppercase(), EcLevel::L) {
+        Ok(code) => code
+            .render::<Dense1x2>()
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark)
+            .build()
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
 /// Render full-screen settings view
 pub fn render_settings_view(f: &mut Frame, app: &App, area: Rect) {
     let field_style = |idx: usize| {
@@ -467,6 +731,7 @@ pub fn render_settings_view(f: &mut Frame, app: &App, area: Rect) {
     let label_style = Style::default().fg(Color::DarkGray);
 
     let autostart_value = if app.settings_autostart_enabled { "Enabled" } else { "Disabled" };
+    let inbox_value = if app.config.public_inbox { "Enabled" } else { "Disabled" };
 
     let lines = vec![
         Line::from(""),
@@ -481,10 +746,36 @@ pub fn render_settings_view(f: &mut Frame, app: &App, area: Rect) {
             Span::styled("Polling Interval:  ", label_style),
             Span::styled(format!("{}s", app.settings_polling_interval), field_style(1)),
         ]),
+        Line::from(vec![
+            Span::styled("Max Interval:      ", label_style),
+            Span::styled(format!("{}s", app.settings_max_interval), field_style(2)),
+        ]),
+        Line::from(vec![
+            Span::styled("Background:        ", label_style),
+            Span::styled(format!("{}s", app.settings_background_interval), field_style(3)),
+        ]),
+        Line::from(vec![
+            Span::styled("Backoff Factor:    ", label_style),
+            Span::styled(format!("×{}", app.settings_backoff_factor), field_style(4)),
+        ]),
+        Line::from(vec![
+            Span::styled("Jitter:            ", label_style),
+            Span::styled(format!("±{}", app.settings_jitter), field_style(5)),
+        ]),
+        Line::from(vec![
+            Span::styled("Quiet Hours:       ", label_style),
+            Span::styled(&app.settings_quiet_hours, field_style(6)),
+            Span::styled("  (e.g. 23:00-07:00 900, or off)", label_style),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("Start at Login:    ", label_style),
-            Span::styled(autostart_value, field_style(2)),
+            Span::styled(autostart_value, field_style(SETTINGS_AUTOSTART_FIELD)),
+        ]),
+        Line::from(vec![
+            Span::styled("Public Inbox:      ", label_style),
+            Span::styled(inbox_value, field_style(SETTINGS_INBOX_FIELD)),
+            Span::styled("  (first messages from people who aren't contacts yet)", label_style),
         ]),
         Line::from(""),
         Line::from(vec![